@@ -2,7 +2,9 @@ use anyhow::{Error, Ok};
 use clap::Parser;
 use std::{fmt, str::FromStr};
 
-use crate::{process_decode, process_encode, CmdExcutor};
+use crate::{
+    process_datauri_decode, process_datauri_encode, process_decode, process_encode, CmdExcutor,
+};
 
 use super::verify_input_file;
 
@@ -12,14 +14,51 @@ pub enum Base64SubCommand {
     Encode(Base64EncodeOpts),
     #[command(name = "decode", about = "decode base64")]
     Decode(Base64DecodeOpts),
+    #[command(
+        name = "datauri",
+        about = "Embed a file as a data: uri, or extract one back with --decode"
+    )]
+    DataUri(Base64DataUriOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct Base64DataUriOpts {
+    #[arg(value_parser = verify_input_file, default_value = "-")]
+    pub input: String,
+    #[arg(
+        short,
+        long,
+        help = "Output file, \"-\" for stdout [default: -, or output.<ext> with --decode]"
+    )]
+    pub output: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "decode",
+        help = "Media type, sniffed from the content or extension when omitted"
+    )]
+    pub mime: Option<String>,
+    #[arg(short, long, help = "Read a data uri and write its payload")]
+    pub decode: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_input_file, default_value = "-")]
     pub input: String,
-    #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
+    #[arg(
+        long,
+        value_parser = parse_base64_format,
+        default_value = "standard",
+        help = "standard, standard-nopad, urlsafe, urlsafe-pad or auto to detect the alphabet and padding"
+    )]
     pub format: Base64Format,
+    #[arg(
+        short,
+        long,
+        default_value = "-",
+        help = "Write the raw bytes to a file, \"-\" for stdout"
+    )]
+    pub output: String,
 }
 #[derive(Debug, Parser)]
 pub struct Base64EncodeOpts {
@@ -27,25 +66,48 @@ pub struct Base64EncodeOpts {
     pub input: String,
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
+    #[arg(
+        short,
+        long,
+        default_value = "-",
+        help = "Output file, \"-\" for stdout"
+    )]
+    pub output: String,
+    #[arg(long, value_parser = parse_wrap, help = "Break lines after this many characters, e.g. 76 for MIME or 64 for PEM")]
+    pub wrap: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Base64Format {
     Standard,
+    StandardNoPad,
     UrlSafe,
+    UrlSafePad,
+    // 仅用于解码
+    Auto,
 }
 
 fn parse_base64_format(format: &str) -> Result<Base64Format, anyhow::Error> {
     format.parse()
 }
 
+fn parse_wrap(wrap: &str) -> Result<usize, anyhow::Error> {
+    match wrap.parse()? {
+        0 => Err(anyhow::anyhow!("Wrap width must be greater than 0")),
+        width => Ok(width),
+    }
+}
+
 impl FromStr for Base64Format {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -55,7 +117,10 @@ impl From<Base64Format> for &'static str {
     fn from(format: Base64Format) -> Self {
         match format {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Auto => "auto",
         }
     }
 }
@@ -68,17 +133,28 @@ impl fmt::Display for Base64Format {
 
 impl CmdExcutor for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let encode = process_encode(&self.input, self.format)?;
-        println!("{}", encode);
-        Ok(())
+        // 解码结果可能是二进制，原样写出
+        process_decode(&self.input, &self.output, self.format)
     }
 }
 
 impl CmdExcutor for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let decode = process_decode(&self.input, self.format)?;
-        println!("{}", decode);
-        Ok(())
+        process_encode(&self.input, &self.output, self.format, self.wrap)
+    }
+}
+
+impl CmdExcutor for Base64DataUriOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if self.decode {
+            let (media_type, output) = process_datauri_decode(&self.input, self.output.as_deref())?;
+            // stdout 可能是数据本身，提示信息写到 stderr
+            eprintln!("{} written to {}", media_type, output);
+            Ok(())
+        } else {
+            let output = self.output.as_deref().unwrap_or("-");
+            process_datauri_encode(&self.input, output, self.mime.as_deref())
+        }
     }
 }
 
@@ -87,6 +163,7 @@ impl CmdExcutor for Base64SubCommand {
         match self {
             Base64SubCommand::Encode(opts) => opts.execute().await,
             Base64SubCommand::Decode(opts) => opts.execute().await,
+            Base64SubCommand::DataUri(opts) => opts.execute().await,
         }
     }
 }
@@ -0,0 +1,51 @@
+use crate::{process_convert, CmdExcutor, OutputFormat};
+use clap::Parser;
+use std::path::Path;
+
+use super::{
+    csv::{parse_byte, parse_format},
+    verify_input_file,
+};
+
+#[derive(Debug, Parser)]
+pub struct ConvertOpts {
+    #[arg(short, long, value_parser = verify_input_file)]
+    pub input: String,
+    #[arg(
+        short,
+        long,
+        default_value = "output.csv",
+        help = "Output file, \"-\" for stdout"
+    )]
+    pub output: String,
+    #[arg(
+        long,
+        value_parser = parse_format,
+        help = "Input format, detected from the file extension when omitted"
+    )]
+    pub from: Option<OutputFormat>,
+    #[arg(short, long, value_parser = parse_byte, default_value = ",")]
+    pub delimiter: u8,
+    #[arg(long, help = "Key of the array of tables in toml input")]
+    pub toml_key: Option<String>,
+}
+
+impl CmdExcutor for ConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let format = match self.from {
+            Some(format) => format,
+            None => Path::new(&self.input)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .ok_or_else(|| anyhow::anyhow!("Cannot detect input format, use --from"))?
+                .parse()?,
+        };
+        process_convert(
+            &self.input,
+            &self.output,
+            format,
+            self.delimiter,
+            self.toml_key.as_deref(),
+        )
+    }
+}
@@ -1,23 +1,258 @@
+use crate::{
+    process_csv, process_csv_concat, process_csv_diff, process_csv_join, process_csv_stats,
+    process_csv_validate, process_records, CmdExcutor,
+};
+use clap::{ArgAction, Args, Parser};
+use encoding_rs::Encoding;
 use std::{fmt, str::FromStr};
-use clap::Parser;
-use crate::{process_csv, CmdExcutor};
 
 use super::verify_input_file;
 
+// `rcli csv -i ...` 直接转换，其它功能以子命令提供
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvOpt {
-    #[arg(short, long,value_parser = verify_input_file)]
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+    #[command(flatten)]
+    pub convert: CsvConvertOpts,
+}
+
+#[derive(Debug, Parser)]
+pub enum CsvSubCommand {
+    #[command(about = "Validate a csv file against a yaml/json schema")]
+    Validate(CsvValidateOpts),
+    #[command(about = "Profile the columns of a csv file")]
+    Stats(CsvStatsOpts),
+    #[command(about = "Merge rows of two csv files on a key column")]
+    Join(CsvJoinOpts),
+    #[command(about = "Stack csv files with the same columns")]
+    Concat(CsvConcatOpts),
+    #[command(about = "Show added, removed and changed rows between two csv files")]
+    Diff(CsvDiffOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDiffOpts {
+    #[arg(value_parser = verify_input_file)]
+    pub old: String,
+    #[arg(value_parser = verify_input_file)]
+    pub new: String,
+    #[arg(short, long, help = "Column that identifies a row in both files")]
+    pub key: String,
+    #[arg(short, long, value_parser = parse_format, help = "Output format, a summary when omitted")]
+    pub format: Option<OutputFormat>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvJoinOpts {
+    #[arg(value_parser = verify_input_file)]
+    pub left: String,
+    #[arg(value_parser = verify_input_file)]
+    pub right: String,
+    #[arg(long, help = "Key column, must exist in both files")]
+    pub on: String,
+    #[arg(long, value_parser = parse_join_how, default_value = "left")]
+    pub how: JoinHow,
+    #[arg(
+        long,
+        default_value = "_right",
+        help = "Appended to right columns whose names clash with the left ones"
+    )]
+    pub suffix: String,
+    #[arg(short, long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+    #[command(flatten)]
+    pub types: CsvTypeOpts,
+    #[command(flatten)]
+    pub query: CsvQueryOpts,
+    #[command(flatten)]
+    pub writer: CsvWriteOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvConcatOpts {
+    #[arg(value_parser = verify_input_file, num_args = 1.., required = true)]
+    pub inputs: Vec<String>,
+    #[arg(short, long, value_parser = parse_format, default_value = "json")]
+    pub format: OutputFormat,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+    #[command(flatten)]
+    pub types: CsvTypeOpts,
+    #[command(flatten)]
+    pub query: CsvQueryOpts,
+    #[command(flatten)]
+    pub writer: CsvWriteOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvStatsOpts {
+    #[arg(short, long, value_parser = verify_input_file)]
+    pub input: String,
+    #[arg(short, long, value_parser = parse_format, help = "Output format, a table when omitted")]
+    pub format: Option<OutputFormat>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Number of most frequent values to show"
+    )]
+    pub top: usize,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+    #[command(flatten)]
+    pub types: CsvTypeOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvValidateOpts {
+    #[arg(short, long, value_parser = verify_input_file)]
     pub input: String,
+    #[arg(short, long, value_parser = verify_input_file)]
+    pub schema: String,
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+}
+
+#[derive(Debug, Args)]
+pub struct CsvConvertOpts {
+    // 有子命令时不要求 --input
+    #[arg(short, long, value_parser = verify_input_file, required = true)]
+    pub input: Option<String>,
     #[arg(short, long, value_parser = parse_format, default_value = "json")]
     pub format: OutputFormat,
-    #[arg(short, long)]
+    #[arg(
+        short,
+        long,
+        help = "Output file, \"-\" for stdout [default: output.<format>]"
+    )]
     pub output: Option<String>,
-     // default_value 会做一次自动转换 from &str to String
+    #[command(flatten)]
+    pub reader: CsvReaderOpts,
+    #[command(flatten)]
+    pub types: CsvTypeOpts,
+    #[command(flatten)]
+    pub query: CsvQueryOpts,
+    #[command(flatten)]
+    pub writer: CsvWriteOpts,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvWriteOpts {
+    #[arg(
+        long,
+        default_value = "records",
+        help = "Name of the array of tables in toml output"
+    )]
+    pub toml_key: String,
+    #[arg(
+        long,
+        help = "Write each record as it is read, yaml becomes a multi-document stream"
+    )]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvQueryOpts {
+    #[arg(long, value_delimiter = ',', help = "Columns to output, in order")]
+    pub select: Vec<String>,
+    #[arg(
+        long = "where",
+        value_parser = parse_conditions,
+        help = "Filter records, e.g. 'Nationality == \"Italy\" && Kit Number > 10'"
+    )]
+    pub filters: Vec<Conditions>,
+    #[arg(long, help = "Sort records by a column, reads the whole file")]
+    pub sort_by: Option<String>,
+    #[arg(long, requires = "sort_by", help = "Sort in descending order")]
+    pub desc: bool,
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Conditions(pub Vec<Condition>);
+
+#[derive(Debug, Clone)]
+pub struct Condition {
+    pub column: String,
+    pub op: CompareOp,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvReaderOpts {
+    // default_value 会做一次自动转换 from &str to String
     // default_value_t 不会自动转换，需要和字段类型完全对应
-    #[arg(short, long, default_value_t = ',')]
-    pub delimiter: char,
-    #[arg(long, default_value_t = true)]
+    #[arg(short, long, value_parser = parse_byte, default_value = ",")]
+    pub delimiter: u8,
+    #[arg(long, value_parser = parse_byte, default_value = "\"")]
+    pub quote: u8,
+    #[arg(long, value_parser = parse_byte, help = "Escape character, quotes are escaped by doubling when unset")]
+    pub escape: Option<u8>,
+    // ArgAction::Set 使 `--header false` 可用
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
     pub header: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Column names, defaults to the header row or col1, col2, ..."
+    )]
+    pub columns: Vec<String>,
+    #[arg(
+        long,
+        value_parser = parse_encoding,
+        help = "Input encoding, e.g. windows-1252 or utf-16le [default: utf-8, or from the BOM]"
+    )]
+    pub encoding: Option<&'static Encoding>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvTypeOpts {
+    #[arg(
+        long = "type",
+        value_parser = parse_type_overrides,
+        help = "Column types, e.g. \"Kit Number=int,DOB=date:%b %d, %Y\""
+    )]
+    pub overrides: Vec<TypeOverrides>,
+    #[arg(long, help = "Keep cells as strings unless a column type is given")]
+    pub raw: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeOverrides(pub Vec<(String, CellType)>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellType {
+    Auto,
+    String,
+    Int,
+    Float,
+    Bool,
+    // chrono 格式串，None 表示 ISO 8601
+    Date(Option<String>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -25,6 +260,163 @@ pub enum OutputFormat {
     Json,
     Yaml,
     Toml,
+    Ndjson,
+    Xlsx,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinHow {
+    Left,
+    Inner,
+    Outer,
+}
+
+// 多个条件用 && 连接；列名含空格时可直接书写，也可用引号包裹
+fn parse_conditions(s: &str) -> Result<Conditions, anyhow::Error> {
+    // 引号内的 && 属于值或列名，不作为分隔符
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '"' | '\'' | '`') => quote = Some(c),
+            None if s[i..].starts_with("&&") && i >= start => {
+                parts.push(&s[start..i]);
+                start = i + 2;
+            }
+            None => {}
+        }
+    }
+    parts.push(&s[start..]);
+    let conditions = parts
+        .into_iter()
+        .map(parse_condition)
+        .collect::<Result<_, _>>()?;
+    Ok(Conditions(conditions))
+}
+
+fn parse_condition(s: &str) -> Result<Condition, anyhow::Error> {
+    // 长的运算符在前，避免 ">=" 被识别为 ">"
+    const OPS: [(&str, CompareOp); 7] = [
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        (">=", CompareOp::Ge),
+        ("<=", CompareOp::Le),
+        ("=", CompareOp::Eq),
+        (">", CompareOp::Gt),
+        ("<", CompareOp::Lt),
+    ];
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '"' | '\'' | '`') => quote = Some(c),
+            None => {
+                if let Some((token, op)) = OPS.iter().find(|(token, _)| s[i..].starts_with(token)) {
+                    let column = unquote(s[..i].trim());
+                    if column.is_empty() {
+                        break;
+                    }
+                    return Ok(Condition {
+                        column: column.into(),
+                        op: *op,
+                        value: unquote(s[i + token.len()..].trim()).into(),
+                    });
+                }
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "Invalid condition {:?}, expect <column> <op> <value>",
+        s.trim()
+    ))
+}
+
+fn unquote(s: &str) -> &str {
+    for q in ['"', '\'', '`'] {
+        if let Some(inner) = s.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    s
+}
+
+fn parse_encoding(label: &str) -> Result<&'static Encoding, anyhow::Error> {
+    Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| anyhow::anyhow!("Unknown encoding {:?}", label))
+}
+
+pub(crate) fn parse_byte(s: &str) -> Result<u8, &'static str> {
+    match s {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+        _ => Err("Must be a single ascii character"),
+    }
+}
+
+// 日期格式本身可能含有逗号，不含 `=` 的片段归入前一个列
+fn parse_type_overrides(s: &str) -> Result<TypeOverrides, anyhow::Error> {
+    let mut specs: Vec<String> = Vec::new();
+    for part in s.split(',') {
+        match specs.last_mut() {
+            Some(last) if !part.contains('=') => {
+                last.push(',');
+                last.push_str(part);
+            }
+            _ => specs.push(part.into()),
+        }
+    }
+    let overrides = specs
+        .iter()
+        .map(|spec| {
+            let (column, ty) = spec
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expect <column>=<type>, got {:?}", spec))?;
+            Ok((column.trim().to_string(), ty.trim_start().parse()?))
+        })
+        .collect::<Result<_, anyhow::Error>>()?;
+    Ok(TypeOverrides(overrides))
+}
+
+impl FromStr for CellType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(CellType::Auto),
+            "string" | "str" => Ok(CellType::String),
+            "int" => Ok(CellType::Int),
+            "float" => Ok(CellType::Float),
+            "bool" => Ok(CellType::Bool),
+            "date" => Ok(CellType::Date(None)),
+            _ => match s.strip_prefix("date:") {
+                Some(fmt) => Ok(CellType::Date(Some(fmt.into()))),
+                None => Err(anyhow::anyhow!("Invalid column type: {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for CellType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellType::Auto => write!(f, "auto"),
+            CellType::String => write!(f, "string"),
+            CellType::Int => write!(f, "int"),
+            CellType::Float => write!(f, "float"),
+            CellType::Bool => write!(f, "bool"),
+            CellType::Date(None) => write!(f, "date"),
+            CellType::Date(Some(fmt)) => write!(f, "date:{}", fmt),
+        }
+    }
+}
+
+fn parse_join_how(how: &str) -> Result<JoinHow, anyhow::Error> {
+    how.parse()
 }
 
 pub fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
@@ -37,19 +429,39 @@ impl FromStr for OutputFormat {
     fn from_str(format: &str) -> Result<Self, Self::Err> {
         let format = match format {
             "json" => Ok(OutputFormat::Json),
-            "yaml" => Ok(OutputFormat::Yaml),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "toml" => Ok(OutputFormat::Toml),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "parquet" => Ok(OutputFormat::Parquet),
             _ => Err(anyhow::anyhow!("Output format doesnt exist")),
         };
         format
     }
 }
 
+impl FromStr for JoinHow {
+    type Err = anyhow::Error;
+
+    fn from_str(how: &str) -> Result<Self, Self::Err> {
+        match how {
+            "left" => Ok(JoinHow::Left),
+            "inner" => Ok(JoinHow::Inner),
+            "outer" => Ok(JoinHow::Outer),
+            _ => Err(anyhow::anyhow!("Join must be one of left, inner, outer")),
+        }
+    }
+}
+
 impl From<OutputFormat> for &'static str {
     fn from(value: OutputFormat) -> Self {
         match value {
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
             OutputFormat::Toml => "toml",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Parquet => "parquet",
         }
     }
 }
@@ -60,14 +472,126 @@ impl fmt::Display for OutputFormat {
     }
 }
 
-
 impl CmdExcutor for CsvOpt {
     async fn execute(self) -> anyhow::Result<()> {
+        match self.cmd {
+            Some(cmd) => cmd.execute().await,
+            None => self.convert.execute().await,
+        }
+    }
+}
+
+impl CmdExcutor for CsvSubCommand {
+    async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            CsvSubCommand::Validate(opts) => opts.execute().await,
+            CsvSubCommand::Stats(opts) => opts.execute().await,
+            CsvSubCommand::Join(opts) => opts.execute().await,
+            CsvSubCommand::Concat(opts) => opts.execute().await,
+            CsvSubCommand::Diff(opts) => opts.execute().await,
+        }
+    }
+}
+
+impl CmdExcutor for CsvValidateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (rows, violations) = process_csv_validate(&self.input, &self.schema, &self.reader)?;
+        for violation in &violations {
+            println!("{}", violation);
+        }
+        if !violations.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} violations found in {} rows",
+                violations.len(),
+                rows
+            ));
+        }
+        println!("{} rows valid", rows);
+        Ok(())
+    }
+}
+
+impl CmdExcutor for CsvStatsOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_stats(
+            &self.input,
+            &self.output,
+            self.format,
+            &self.reader,
+            &self.types,
+            self.top,
+        )
+    }
+}
+
+impl CmdExcutor for CsvConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let input = self
+            .input
+            .ok_or_else(|| anyhow::anyhow!("Missing --input"))?;
         let output = if let Some(output) = self.output {
             output
         } else {
             format!("output.{}", self.format)
         };
-        process_csv(&self.input, &output, self.format)
+        process_csv(
+            &input,
+            &output,
+            self.format,
+            &self.reader,
+            &self.types,
+            &self.query,
+            &self.writer,
+        )
+    }
+}
+
+impl CmdExcutor for CsvJoinOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (headers, records) = process_csv_join(
+            &self.left,
+            &self.right,
+            &self.reader,
+            &self.on,
+            self.how,
+            &self.suffix,
+        )?;
+        process_records(
+            &self.output,
+            &headers,
+            records,
+            self.format,
+            &self.types,
+            &self.query,
+            &self.writer,
+        )
+    }
+}
+
+impl CmdExcutor for CsvConcatOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let (headers, records) = process_csv_concat(&self.inputs, &self.reader)?;
+        process_records(
+            &self.output,
+            &headers,
+            records,
+            self.format,
+            &self.types,
+            &self.query,
+            &self.writer,
+        )
+    }
+}
+
+impl CmdExcutor for CsvDiffOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_diff(
+            &self.old,
+            &self.new,
+            &self.key,
+            &self.output,
+            self.format,
+            &self.reader,
+        )
     }
 }
@@ -0,0 +1,97 @@
+use crate::{process_codec, Base64Format, CmdExcutor};
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+use super::verify_input_file;
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short, long, value_parser = verify_input_file, default_value = "-")]
+    pub input: String,
+    #[arg(
+        short,
+        long,
+        default_value = "-",
+        help = "Output file, \"-\" for stdout"
+    )]
+    pub output: String,
+    #[arg(
+        short,
+        long,
+        value_parser = parse_codec,
+        default_value = "base64",
+        help = "base64, base64-nopad, base64url, base64url-pad, base64-auto (decode only), base32, base32-nopad, base58, hex, hex-upper, ascii85 or z85"
+    )]
+    pub codec: Codec,
+    #[arg(short, long, help = "Decode the input back to raw bytes")]
+    pub decode: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Codec {
+    Base64(Base64Format),
+    Base32,
+    Base32NoPad,
+    Base58,
+    Hex,
+    HexUpper,
+    Ascii85,
+    Z85,
+}
+
+fn parse_codec(codec: &str) -> Result<Codec, anyhow::Error> {
+    codec.parse()
+}
+
+impl FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(codec: &str) -> Result<Self, Self::Err> {
+        match codec.to_ascii_lowercase().as_str() {
+            "base64" => Ok(Codec::Base64(Base64Format::Standard)),
+            "base64-nopad" => Ok(Codec::Base64(Base64Format::StandardNoPad)),
+            "base64url" => Ok(Codec::Base64(Base64Format::UrlSafe)),
+            "base64url-pad" => Ok(Codec::Base64(Base64Format::UrlSafePad)),
+            "base64-auto" => Ok(Codec::Base64(Base64Format::Auto)),
+            "base32" => Ok(Codec::Base32),
+            "base32-nopad" => Ok(Codec::Base32NoPad),
+            "base58" => Ok(Codec::Base58),
+            "hex" => Ok(Codec::Hex),
+            "hex-upper" => Ok(Codec::HexUpper),
+            "ascii85" => Ok(Codec::Ascii85),
+            "z85" => Ok(Codec::Z85),
+            _ => Err(anyhow::anyhow!("Unsupported codec: {}", codec)),
+        }
+    }
+}
+
+impl From<Codec> for &'static str {
+    fn from(codec: Codec) -> Self {
+        match codec {
+            Codec::Base64(Base64Format::Standard) => "base64",
+            Codec::Base64(Base64Format::StandardNoPad) => "base64-nopad",
+            Codec::Base64(Base64Format::UrlSafe) => "base64url",
+            Codec::Base64(Base64Format::UrlSafePad) => "base64url-pad",
+            Codec::Base64(Base64Format::Auto) => "base64-auto",
+            Codec::Base32 => "base32",
+            Codec::Base32NoPad => "base32-nopad",
+            Codec::Base58 => "base58",
+            Codec::Hex => "hex",
+            Codec::HexUpper => "hex-upper",
+            Codec::Ascii85 => "ascii85",
+            Codec::Z85 => "z85",
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl CmdExcutor for EncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_codec(&self.input, &self.output, self.codec, self.decode)
+    }
+}
@@ -1,29 +1,178 @@
-use crate::{process_genpass, CmdExcutor};
-use clap::Parser;
+use crate::{process_genpass, process_genpass_batch, process_genphrase, CmdExcutor, HashAlgorithm};
+use clap::{Args, Parser};
+use std::{fmt, str::FromStr};
+
+use super::{passwd::parse_hash_algorithm, verify_input_file};
+
 #[derive(Debug, Parser)]
 pub struct GenPassOpts {
-    #[arg(short, long, default_value_t = 16)]
-    pub length: u8,
-    #[arg(long, default_value_t = true)]
-    pub uppercase: bool,
-    #[arg(long, default_value_t = true)]
-    pub lowercase: bool,
-    #[arg(long, default_value_t = true)]
-    pub number: bool,
-    #[arg(long, default_value_t = true)]
-    pub symbol: bool,
+    #[arg(short, long, value_parser = parse_count, default_value_t = 16)]
+    pub length: usize,
+    #[arg(
+        long,
+        conflicts_with = "min_upper",
+        help = "Leave out uppercase letters"
+    )]
+    pub no_uppercase: bool,
+    #[arg(
+        long,
+        conflicts_with = "min_lower",
+        help = "Leave out lowercase letters"
+    )]
+    pub no_lowercase: bool,
+    #[arg(long, conflicts_with = "min_digits", help = "Leave out digits")]
+    pub no_number: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["min_symbols", "symbols"],
+        help = "Leave out symbols"
+    )]
+    pub no_symbol: bool,
+    #[arg(
+        long,
+        value_parser = parse_count,
+        conflicts_with = "length",
+        help = "Generate a passphrase of this many words instead"
+    )]
+    pub words: Option<usize>,
+    #[arg(long, default_value = "-", requires = "words")]
+    pub separator: String,
+    #[arg(
+        long,
+        value_parser = verify_input_file,
+        requires = "words",
+        help = "Wordlist file, one word per line [default: EFF large wordlist]"
+    )]
+    pub wordlist: Option<String>,
+    #[arg(long, requires = "words", help = "Capitalize every word")]
+    pub capitalize: bool,
+    #[arg(long, requires = "words", help = "Append a digit to a random word")]
+    pub add_digit: bool,
+    #[arg(long, requires = "words", help = "Append a symbol to a random word")]
+    pub add_symbol: bool,
+    #[arg(long, value_parser = parse_count, default_value_t = 1, help = "Number of unique passwords")]
+    pub count: usize,
+    #[arg(long, value_parser = parse_password_format, default_value = "text")]
+    pub output_format: PasswordFormat,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(long, help = "Include the entropy in bits of each password")]
+    pub show_entropy: bool,
+    #[arg(
+        long,
+        value_parser = parse_hash_algorithm,
+        help = "Include a hash of each password: argon2, bcrypt, scrypt or pbkdf2"
+    )]
+    pub hash: Option<HashAlgorithm>,
+    #[command(flatten)]
+    pub charset: CharsetOpts,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct CharsetOpts {
+    #[arg(
+        long,
+        conflicts_with_all = ["symbols", "min_upper", "min_lower", "min_digits", "min_symbols"],
+        help = "Generate from exactly these characters instead of the classes"
+    )]
+    pub charset: Option<String>,
+    #[arg(
+        long,
+        default_value = "",
+        hide_default_value = true,
+        help = "Characters to leave out"
+    )]
+    pub exclude: String,
+    #[arg(long, help = "Symbols to use instead of the default set")]
+    pub symbols: Option<String>,
+    #[arg(long, help = "Leave out look-alike characters: 0 O 1 l I |")]
+    pub no_ambiguous: bool,
+    // 每个启用的字符类至少一个，--min-* 可提高下限
+    #[arg(long, default_value_t = 0)]
+    pub min_upper: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_lower: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_digits: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_symbols: usize,
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".into()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PasswordFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+fn parse_password_format(format: &str) -> Result<PasswordFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl FromStr for PasswordFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(PasswordFormat::Text),
+            "csv" => Ok(PasswordFormat::Csv),
+            "json" => Ok(PasswordFormat::Json),
+            _ => Err(anyhow::anyhow!("Output format must be text, csv or json")),
+        }
+    }
+}
+
+impl From<PasswordFormat> for &'static str {
+    fn from(format: PasswordFormat) -> Self {
+        match format {
+            PasswordFormat::Text => "text",
+            PasswordFormat::Csv => "csv",
+            PasswordFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for PasswordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&'static str>::into(*self))
+    }
 }
 
 impl CmdExcutor for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let password = process_genpass(
-            self.length,
-            self.uppercase,
-            self.lowercase,
-            self.number,
-            self.symbol,
-        )?;
-        println!("{:?}", password);
-        Ok(())
+        let generate = || match self.words {
+            Some(words) => process_genphrase(
+                words,
+                &self.separator,
+                self.wordlist.as_deref(),
+                self.capitalize,
+                self.add_digit,
+                self.add_symbol,
+            ),
+            None => process_genpass(
+                self.length,
+                !self.no_uppercase,
+                !self.no_lowercase,
+                !self.no_number,
+                !self.no_symbol,
+                &self.charset,
+            ),
+        };
+        process_genpass_batch(
+            &self.output,
+            self.output_format,
+            self.count,
+            self.show_entropy,
+            self.hash,
+            generate,
+        )
     }
 }
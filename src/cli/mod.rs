@@ -1,15 +1,26 @@
 mod base64;
+mod convert;
 mod csv;
+mod encode;
 mod genpass;
 mod http;
+mod otp;
+mod passwd;
 mod text;
 mod jwt;
-use self::{csv::CsvOpt, genpass::GenPassOpts, jwt::JwtSubCommand};
+use self::{convert::ConvertOpts, csv::CsvOpt, genpass::GenPassOpts, jwt::JwtSubCommand};
+pub use genpass::{CharsetOpts, PasswordFormat};
 use crate::CmdExcutor;
 pub use base64::{Base64Format, Base64SubCommand};
+pub use encode::{Codec, EncodeOpts};
 use clap::Parser;
-pub use csv::OutputFormat;
+pub use csv::{
+    CellType, CompareOp, Condition, CsvQueryOpts, CsvReaderOpts, CsvTypeOpts, CsvWriteOpts, JoinHow,
+    OutputFormat,
+};
 pub use http::HttpSubCommand;
+pub use otp::{OtpAlgorithm, OtpOpts, OtpSubCommand};
+pub use passwd::{HashAlgorithm, HashParams, PasswdSubCommand};
 use std::path::Path;
 pub use text::{TextSignFormat, TextSubCommand};
 
@@ -22,18 +33,29 @@ pub struct Opts {
 
 #[derive(Debug, Parser)]
 pub enum Subcommand {
-    #[command(name = "csv", about = "Convert Csv to json")]
-    Csv(CsvOpt),
+    #[command(name = "csv", about = "Convert, validate, profile, merge or diff csv files")]
+    Csv(Box<CsvOpt>),
+    #[command(name = "convert", about = "Convert json/yaml/toml/ndjson to csv")]
+    Convert(ConvertOpts),
     #[command(name = "genpass", about = "Generate a random password")]
     GenPass(GenPassOpts),
+    #[command(subcommand, about = "Check, hash or verify passwords")]
+    Passwd(PasswdSubCommand),
     #[command(subcommand, about = "Encode or decode base64")]
     Base64(Base64SubCommand),
+    #[command(
+        name = "encode",
+        about = "Encode or decode base64/base32/base58/hex/base85"
+    )]
+    Encode(EncodeOpts),
     #[command(subcommand, about = "Text sign/verify")]
     Text(TextSubCommand),
     #[command(subcommand, about = "Http Serve")]
     Http(HttpSubCommand),
     #[command(subcommand, about = "Jwt sign/verify")]
     Jwt(JwtSubCommand),
+    #[command(subcommand, about = "TOTP/HOTP secrets, codes and verification")]
+    Otp(OtpSubCommand),
 }
 
 pub fn verify_input_file(filename: &str) -> Result<String, &'static str> {
@@ -47,12 +69,16 @@ pub fn verify_input_file(filename: &str) -> Result<String, &'static str> {
 impl CmdExcutor for Subcommand {
     async fn execute(self) -> anyhow::Result<()> {
         match self {
-            Subcommand::Csv(opts) => opts.execute().await,
+            Subcommand::Csv(opts) => (*opts).execute().await,
+            Subcommand::Convert(opts) => opts.execute().await,
             Subcommand::GenPass(opts) => opts.execute().await,
+            Subcommand::Passwd(opts) => opts.execute().await,
             Subcommand::Base64(opts) => opts.execute().await,
+            Subcommand::Encode(opts) => opts.execute().await,
             Subcommand::Text(opts) => opts.execute().await,
             Subcommand::Http(opts) => opts.execute().await,
             Subcommand::Jwt(opts) => opts.execute().await,
+            Subcommand::Otp(opts) => opts.execute().await,
         }
     }
 }
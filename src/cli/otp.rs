@@ -0,0 +1,167 @@
+use crate::{
+    process_otp_code, process_otp_secret, process_otp_uri, process_otp_verify, CmdExcutor,
+};
+use clap::{Args, Parser};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+pub enum OtpSubCommand {
+    #[command(about = "Generate a base32 shared secret")]
+    Secret(OtpSecretOpts),
+    #[command(about = "Print the otpauth:// provisioning uri")]
+    Uri(OtpUriOpts),
+    #[command(about = "Compute the current TOTP, or a HOTP code with --counter")]
+    Code(OtpCodeOpts),
+    #[command(about = "Verify a code within a window of time steps or counters")]
+    Verify(OtpVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpSecretOpts {
+    #[arg(
+        long,
+        default_value_t = 20,
+        value_parser = clap::value_parser!(u16).range(16..=64),
+        help = "Number of random bytes, RFC 4226 requires at least 16"
+    )]
+    pub bytes: u16,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpUriOpts {
+    #[arg(long)]
+    pub issuer: String,
+    #[arg(long, help = "Account name, e.g. an email address")]
+    pub account: String,
+    #[command(flatten)]
+    pub otp: OtpOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpCodeOpts {
+    #[arg(long, help = "Unix time to compute the TOTP for [default: now]")]
+    pub time: Option<u64>,
+    #[command(flatten)]
+    pub otp: OtpOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    pub code: String,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Accepted time steps before and after now, or counters after --counter"
+    )]
+    pub window: u64,
+    #[arg(long, help = "Unix time to verify the TOTP at [default: now]")]
+    pub time: Option<u64>,
+    #[command(flatten)]
+    pub otp: OtpOpts,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct OtpOpts {
+    #[arg(
+        short,
+        long,
+        help = "Base32 shared secret, spaces and case are ignored"
+    )]
+    pub secret: String,
+    #[arg(long, value_parser = parse_otp_algorithm, default_value = "sha1")]
+    pub algorithm: OtpAlgorithm,
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub period: u64,
+    #[arg(long, help = "Use HOTP with this counter instead of TOTP")]
+    pub counter: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_otp_algorithm(algorithm: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(algorithm: &str) -> Result<Self, Self::Err> {
+        match algorithm.to_ascii_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Algorithm must be sha1, sha256 or sha512")),
+        }
+    }
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algorithm: OtpAlgorithm) -> Self {
+        // otpauth uri 中使用大写
+        match algorithm {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&'static str>::into(*self))
+    }
+}
+
+impl CmdExcutor for OtpSecretOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        println!("{}", process_otp_secret(self.bytes as usize)?);
+        Ok(())
+    }
+}
+
+impl CmdExcutor for OtpUriOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        println!(
+            "{}",
+            process_otp_uri(&self.otp, &self.issuer, &self.account)?
+        );
+        Ok(())
+    }
+}
+
+impl CmdExcutor for OtpCodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        println!("{}", process_otp_code(&self.otp, self.time)?);
+        Ok(())
+    }
+}
+
+impl CmdExcutor for OtpVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        match process_otp_verify(&self.otp, &self.code, self.window, self.time)? {
+            Some(counter) => {
+                println!("Code is valid (counter {})", counter);
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!("Code is not valid")),
+        }
+    }
+}
+
+impl CmdExcutor for OtpSubCommand {
+    async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            OtpSubCommand::Secret(opts) => opts.execute().await,
+            OtpSubCommand::Uri(opts) => opts.execute().await,
+            OtpSubCommand::Code(opts) => opts.execute().await,
+            OtpSubCommand::Verify(opts) => opts.execute().await,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{process_passwd_check, process_passwd_hash, process_passwd_verify, CmdExcutor};
+use clap::{Args, Parser};
+use std::{
+    fmt,
+    io::{self, BufRead, IsTerminal},
+    str::FromStr,
+};
+
+#[derive(Debug, Parser)]
+pub enum PasswdSubCommand {
+    #[command(about = "Estimate the strength of a password")]
+    Check(PasswdCheckOpts),
+    #[command(about = "Hash a password into a PHC string")]
+    Hash(PasswdHashOpts),
+    #[command(about = "Verify a password against a PHC or bcrypt hash")]
+    Verify(PasswdVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct PasswdHashOpts {
+    #[arg(
+        short,
+        long,
+        value_parser = parse_hash_algorithm,
+        default_value = "argon2",
+        help = "argon2, bcrypt, scrypt or pbkdf2"
+    )]
+    pub algorithm: HashAlgorithm,
+    #[command(flatten)]
+    pub params: HashParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct PasswdVerifyOpts {
+    #[arg(help = "Hash to verify against, the algorithm is taken from it")]
+    pub hash: String,
+}
+
+/// Cost parameters, the algorithm's recommended value is used when omitted.
+#[derive(Debug, Clone, Default, Args)]
+pub struct HashParams {
+    #[arg(long, help = "bcrypt cost, or scrypt log2(N)")]
+    pub cost: Option<u32>,
+    #[arg(long, help = "argon2 memory in KiB")]
+    pub memory: Option<u32>,
+    #[arg(long, help = "argon2 passes, or pbkdf2 rounds")]
+    pub iterations: Option<u32>,
+    #[arg(long, help = "argon2 lanes, or scrypt p")]
+    pub parallelism: Option<u32>,
+    #[arg(long, help = "scrypt block size r")]
+    pub block_size: Option<u32>,
+}
+
+#[derive(Debug, Parser)]
+pub struct PasswdCheckOpts {
+    #[arg(
+        long = "user-input",
+        help = "Words the password should not be built from, e.g. the user name"
+    )]
+    pub user_inputs: Vec<String>,
+    #[arg(long, help = "Print the report as json")]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Argon2,
+    Bcrypt,
+    Scrypt,
+    Pbkdf2,
+}
+
+pub fn parse_hash_algorithm(algorithm: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(algorithm: &str) -> Result<Self, Self::Err> {
+        match algorithm {
+            "argon2" | "argon2id" => Ok(HashAlgorithm::Argon2),
+            "bcrypt" => Ok(HashAlgorithm::Bcrypt),
+            "scrypt" => Ok(HashAlgorithm::Scrypt),
+            "pbkdf2" | "pbkdf2-sha256" => Ok(HashAlgorithm::Pbkdf2),
+            _ => Err(anyhow::anyhow!("Unsupported hash algorithm")),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Argon2 => "argon2",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+            HashAlgorithm::Pbkdf2 => "pbkdf2",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&'static str>::into(*self))
+    }
+}
+
+impl CmdExcutor for PasswdCheckOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let password = read_password(false)?;
+        let user_inputs = self
+            .user_inputs
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let report = process_passwd_check(&password, &user_inputs)?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print!("{}", report);
+        }
+        Ok(())
+    }
+}
+
+impl CmdExcutor for PasswdHashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let password = read_password(true)?;
+        let hash = process_passwd_hash(&password, self.algorithm, &self.params)?;
+        println!("{}", hash);
+        Ok(())
+    }
+}
+
+impl CmdExcutor for PasswdVerifyOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let password = read_password(false)?;
+        if process_passwd_verify(&password, &self.hash)? {
+            println!("Password matches");
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Password does not match"))
+        }
+    }
+}
+
+// 终端上隐藏输入；管道输入时读取第一行，避免密码留在 shell 历史中
+fn read_password(confirm: bool) -> anyhow::Result<String> {
+    if !io::stdin().is_terminal() {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+    let password = rpassword::prompt_password("Password: ")?;
+    if confirm && rpassword::prompt_password("Confirm password: ")? != password {
+        return Err(anyhow::anyhow!("Passwords do not match"));
+    }
+    Ok(password)
+}
+
+impl CmdExcutor for PasswdSubCommand {
+    async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            PasswdSubCommand::Check(opts) => opts.execute().await,
+            PasswdSubCommand::Hash(opts) => opts.execute().await,
+            PasswdSubCommand::Verify(opts) => opts.execute().await,
+        }
+    }
+}
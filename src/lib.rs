@@ -1,21 +1,38 @@
 mod cli;
 mod process;
 mod utils;
-pub use process::process_csv;
-pub use process::process_genpass;
+pub use process::process_convert;
+pub use process::{
+    process_csv, process_csv_concat, process_csv_diff, process_csv_join, process_csv_stats, process_csv_validate,
+    process_records,
+};
+pub use process::{
+    process_genpass, process_genpass_batch, process_genphrase, Password, PasswordEntry,
+};
+pub use process::{process_otp_code, process_otp_secret, process_otp_uri, process_otp_verify};
+pub use process::{process_passwd_check, process_passwd_hash, process_passwd_verify, Strength};
 pub use process::process_sign;
 pub use process::process_verify;
 pub use process::process_key_generate;
 pub use process::{process_decode,process_encode};
+pub use process::process_codec;
+pub use process::{process_datauri_decode, process_datauri_encode};
 pub use process::process_http_serve;
 pub use process::{process_jwt_sign,process_jwt_verify};
 pub use cli::OutputFormat;
+pub use cli::{
+    CellType, CompareOp, Condition, CsvQueryOpts, CsvReaderOpts, CsvTypeOpts, CsvWriteOpts, JoinHow,
+};
+pub use cli::{CharsetOpts, PasswordFormat};
 pub use cli::Opts;
 pub use cli::Subcommand;
-pub use cli::Base64SubCommand;
+pub use cli::{Base64Format, Base64SubCommand};
+pub use cli::{Codec, EncodeOpts};
 pub use cli::TextSubCommand;
 pub use cli::TextSignFormat;
 pub use cli::HttpSubCommand;
+pub use cli::{OtpAlgorithm, OtpOpts, OtpSubCommand};
+pub use cli::{HashAlgorithm, HashParams, PasswdSubCommand};
 pub use utils::*;
 
 #[allow(async_fn_in_trait)]
@@ -1,34 +1,177 @@
-use anyhow::{Ok, Result};
+use anyhow::{anyhow, Ok, Result};
 use base64::{
-    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
-    Engine as _,
+    alphabet,
+    engine::{
+        general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+        DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig,
+    },
+    read::DecoderReader,
+    write::EncoderWriter,
 };
-use crate::{cli::Base64Format, get_reader};
-
-pub fn process_encode(input: &str, format: Base64Format) -> Result<String> {
-    let mut reader = get_reader(input)?;
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
-    };
-    Ok(encoded)
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use crate::{cli::Base64Format, get_reader, get_writer};
+
+// 分块读写，内存占用与输入大小无关
+pub(super) const BUF_SIZE: usize = 64 * 1024;
+
+// auto 模式下 url-safe 字符先转换为标准字母表，padding 可有可无
+const AUTO: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// `wrap` breaks the output into lines of that many characters.
+pub fn process_encode(
+    input: &str,
+    output: &str,
+    format: Base64Format,
+    wrap: Option<usize>,
+) -> Result<()> {
+    if let Base64Format::Auto = format {
+        return Err(anyhow!("Format auto can only be used to decode"));
+    }
+    let mut reader = BufReader::with_capacity(BUF_SIZE, get_reader(input)?);
+    let mut writer = BufWriter::with_capacity(BUF_SIZE, get_writer(output)?);
+    encode_stream(&mut reader, &mut writer, format, wrap)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> Result<String> {
-    let mut reader = get_reader(input)?;
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    // avoid accidental newlines
-    let buf = buf.trim();
+/// Line breaks and other whitespace in the input are ignored, so MIME and PEM
+/// bodies decode as is.
+pub fn process_decode(input: &str, output: &str, format: Base64Format) -> Result<()> {
+    let reader = BufReader::with_capacity(BUF_SIZE, get_reader(input)?);
+    let mut writer = BufWriter::with_capacity(BUF_SIZE, get_writer(output)?);
+    decode_stream(reader, &mut writer, format)?;
+    writer.flush()?;
+    Ok(())
+}
 
-    let decoded = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
+pub(super) fn encode_stream(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    format: Base64Format,
+    wrap: Option<usize>,
+) -> Result<()> {
+    let mut wrapped = LineWrap {
+        inner: writer,
+        width: wrap.unwrap_or(usize::MAX),
+        column: 0,
     };
-    // TODO: decoded data might not be string (but for this example, we assume it is)
-    Ok(String::from_utf8(decoded)?)
+    let mut encoder = EncoderWriter::new(&mut wrapped, engine(format));
+    io::copy(reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
 }
 
+pub(super) fn decode_stream(
+    reader: impl Read,
+    writer: &mut impl Write,
+    format: Base64Format,
+) -> Result<()> {
+    let reader = SkipWhitespace(reader);
+    if let Base64Format::Auto = format {
+        let reader = AutoAlphabet {
+            inner: reader,
+            url_safe: None,
+        };
+        io::copy(&mut DecoderReader::new(reader, &AUTO), writer)?;
+    } else {
+        io::copy(&mut DecoderReader::new(reader, engine(format)), writer)?;
+    }
+    Ok(())
+}
+
+fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE_NO_PAD,
+        Base64Format::UrlSafePad => &URL_SAFE,
+        Base64Format::Auto => &AUTO,
+    }
+}
+
+/// Maps the url-safe `-` and `_` onto `+` and `/`, a mix of both alphabets is
+/// rejected rather than guessed.
+struct AutoAlphabet<R> {
+    inner: R,
+    url_safe: Option<bool>,
+}
 
+impl<R: Read> Read for AutoAlphabet<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for b in &mut buf[..n] {
+            let (url_safe, standard) = match *b {
+                b'-' => (true, b'+'),
+                b'_' => (true, b'/'),
+                b'+' | b'/' => (false, *b),
+                _ => continue,
+            };
+            if *self.url_safe.get_or_insert(url_safe) != url_safe {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "input mixes the standard and url-safe base64 alphabets",
+                ));
+            }
+            *b = standard;
+        }
+        io::Result::Ok(n)
+    }
+}
+
+struct LineWrap<W> {
+    inner: W,
+    width: usize,
+    column: usize,
+}
+
+impl<W: Write> Write for LineWrap<W> {
+    // EncoderWriter 遇到部分写入会返回 Ok(0)，这里总是写完整个 buf
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len();
+        while !buf.is_empty() {
+            // 写满一行后再换行，末尾的换行由调用方补上
+            if self.column == self.width {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            let n = buf.len().min(self.width - self.column);
+            self.inner.write_all(&buf[..n])?;
+            self.column += n;
+            buf = &buf[n..];
+        }
+        io::Result::Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Drops ASCII whitespace so trailing newlines do not break decoding.
+pub(super) struct SkipWhitespace<R>(pub(super) R);
+
+impl<R: Read> Read for SkipWhitespace<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.0.read(buf)?;
+            if n == 0 {
+                return io::Result::Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                if !buf[i].is_ascii_whitespace() {
+                    buf[len] = buf[i];
+                    len += 1;
+                }
+            }
+            // 全是空白时继续读，返回 0 会被当作 EOF
+            if len > 0 {
+                return io::Result::Ok(len);
+            }
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use super::b64::{SkipWhitespace, BUF_SIZE};
+use crate::{get_reader, get_writer, process_decode, process_encode, Codec};
+use anyhow::{anyhow, Result};
+use data_encoding::{Encoding, BASE32, BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE, HEXUPPER};
+use std::io::{BufReader, BufWriter, Read, Write};
+
+const Z85: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+// base32 以 5 字节 / 8 字符为一组，hex 以 1 字节 / 2 字符为一组，分块须按组对齐
+const CHUNK_SIZE: usize = 40 * 1024;
+
+/// Encode `input` with `codec`, or decode it back to raw bytes. base64, base32
+/// and hex are streamed, base58 and base85 need the whole input in memory.
+/// Encoded output ends with a newline, whitespace in encoded input is ignored.
+pub fn process_codec(input: &str, output: &str, codec: Codec, decode: bool) -> Result<()> {
+    let encoding = match codec {
+        Codec::Base64(format) if decode => return process_decode(input, output, format),
+        Codec::Base64(format) => return process_encode(input, output, format, None),
+        // 解码时 padding 可有可无，TOTP 密钥通常不带 padding
+        Codec::Base32 | Codec::Base32NoPad if decode => BASE32_NOPAD,
+        Codec::Base32 => BASE32,
+        Codec::Base32NoPad => BASE32_NOPAD,
+        Codec::Hex if decode => HEXLOWER_PERMISSIVE,
+        Codec::Hex => HEXLOWER,
+        Codec::HexUpper if decode => HEXLOWER_PERMISSIVE,
+        Codec::HexUpper => HEXUPPER,
+        Codec::Base58 | Codec::Ascii85 | Codec::Z85 => {
+            return process_whole(input, output, codec, decode)
+        }
+    };
+
+    let mut reader = BufReader::with_capacity(BUF_SIZE, get_reader(input)?);
+    let mut writer = BufWriter::with_capacity(BUF_SIZE, get_writer(output)?);
+    if decode {
+        let mut reader = SkipWhitespace(reader);
+        copy_chunks(&mut reader, &mut writer, |chunk, offset| {
+            // padding 只会出现在最后一组，块按 8 字符对齐所以总在块尾
+            let chunk = match codec {
+                Codec::Base32 | Codec::Base32NoPad => strip_padding(chunk),
+                _ => chunk,
+            };
+            decode_chunk(&encoding, chunk, offset)
+        })?;
+    } else {
+        copy_chunks(&mut reader, &mut writer, |chunk, _| {
+            Ok(encoding.encode(chunk).into_bytes())
+        })?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn process_whole(input: &str, output: &str, codec: Codec, decode: bool) -> Result<()> {
+    let mut data = Vec::new();
+    get_reader(input)?.read_to_end(&mut data)?;
+    let content = if decode {
+        let text = String::from_utf8(data)
+            .map_err(|_| anyhow!("{} input is not valid text", codec))?
+            .split_ascii_whitespace()
+            .collect::<String>();
+        match codec {
+            Codec::Base58 => bs58::decode(&text).into_vec()?,
+            Codec::Ascii85 => decode_ascii85(&text)?,
+            _ => decode_z85(&text)?,
+        }
+    } else {
+        let mut text = match codec {
+            Codec::Base58 => bs58::encode(&data).into_string(),
+            Codec::Ascii85 => encode_ascii85(&data),
+            _ => encode_z85(&data)?,
+        };
+        text.push('\n');
+        text.into_bytes()
+    };
+    let mut writer = get_writer(output)?;
+    writer.write_all(&content)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// 读满一块再处理，保证除最后一块外都按组对齐
+fn copy_chunks(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    mut f: impl FnMut(&[u8], usize) -> Result<Vec<u8>>,
+) -> Result<()> {
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let mut len = 0;
+        while len < buf.len() {
+            match reader.read(&mut buf[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        writer.write_all(&f(&buf[..len], offset)?)?;
+        offset += len;
+    }
+}
+
+fn strip_padding(chunk: &[u8]) -> &[u8] {
+    let len = chunk.iter().rposition(|&b| b != b'=').map_or(0, |i| i + 1);
+    &chunk[..len]
+}
+
+fn decode_chunk(encoding: &Encoding, chunk: &[u8], offset: usize) -> Result<Vec<u8>> {
+    encoding.decode(chunk).map_err(|e| {
+        anyhow!(
+            "{} at offset {} (whitespace excluded)",
+            e.kind,
+            offset + e.position
+        )
+    })
+}
+
+fn base85_digits(mut value: u32) -> [u8; 5] {
+    let mut digits = [0; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = (value % 85) as u8;
+        value /= 85;
+    }
+    digits
+}
+
+fn base85_value(digits: &[u8]) -> Option<u32> {
+    digits
+        .iter()
+        .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d as u32))
+}
+
+/// Adobe/btoa style: `!`..`u`, `z` for a zero group, no `<~ ~>` delimiters.
+fn encode_ascii85(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(4) * 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if value == 0 && chunk.len() == 4 {
+            text.push('z');
+            continue;
+        }
+        // 末尾不足 4 字节时补零编码，只保留 n + 1 个字符
+        let digits = base85_digits(value);
+        text.extend(digits[..chunk.len() + 1].iter().map(|&d| (d + 33) as char));
+    }
+    text
+}
+
+fn decode_ascii85(text: &str) -> Result<Vec<u8>> {
+    let text = text.strip_prefix("<~").unwrap_or(text);
+    let text = text.strip_suffix("~>").unwrap_or(text);
+    let mut data = Vec::with_capacity(text.len() / 5 * 4 + 4);
+    let mut group = Vec::with_capacity(5);
+    for (i, c) in text.bytes().enumerate() {
+        match c {
+            b'z' if group.is_empty() => data.extend([0; 4]),
+            b'!'..=b'u' => {
+                group.push(c - 33);
+                if group.len() == 5 {
+                    let value = base85_value(&group)
+                        .ok_or_else(|| anyhow!("ascii85 group overflows at offset {}", i))?;
+                    data.extend(value.to_be_bytes());
+                    group.clear();
+                }
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Invalid ascii85 character {:?} at offset {}",
+                    c as char,
+                    i
+                ))
+            }
+        }
+    }
+    if !group.is_empty() {
+        if group.len() == 1 {
+            return Err(anyhow!("Truncated ascii85 input"));
+        }
+        // 用最大数字 'u' 补齐，截掉补出来的字节
+        let len = group.len() - 1;
+        group.resize(5, 84);
+        let value = base85_value(&group).ok_or_else(|| anyhow!("ascii85 group overflows"))?;
+        data.extend(&value.to_be_bytes()[..len]);
+    }
+    Ok(data)
+}
+
+/// ZeroMQ Z85 (RFC 32) has no padding, the input must be a multiple of 4 bytes.
+fn encode_z85(data: &[u8]) -> Result<String> {
+    if !data.len().is_multiple_of(4) {
+        return Err(anyhow!(
+            "Z85 input must be a multiple of 4 bytes, got {}",
+            data.len()
+        ));
+    }
+    let mut text = String::with_capacity(data.len() / 4 * 5);
+    for chunk in data.chunks(4) {
+        let value = u32::from_be_bytes(chunk.try_into()?);
+        text.extend(
+            base85_digits(value)
+                .iter()
+                .map(|&d| Z85[d as usize] as char),
+        );
+    }
+    Ok(text)
+}
+
+fn decode_z85(text: &str) -> Result<Vec<u8>> {
+    if !text.len().is_multiple_of(5) {
+        return Err(anyhow!(
+            "Z85 input must be a multiple of 5 characters, got {}",
+            text.len()
+        ));
+    }
+    let mut data = Vec::with_capacity(text.len() / 5 * 4);
+    for (i, chunk) in text.as_bytes().chunks(5).enumerate() {
+        let digits = chunk
+            .iter()
+            .enumerate()
+            .map(|(j, c)| {
+                Z85.iter()
+                    .position(|z| z == c)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Invalid z85 character {:?} at offset {}",
+                            *c as char,
+                            i * 5 + j
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let value = base85_value(&digits)
+            .ok_or_else(|| anyhow!("z85 group overflows at offset {}", i * 5))?;
+        data.extend(value.to_be_bytes());
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_decode, Base64Format};
+    use std::{fs, path::PathBuf};
+
+    const CODECS: [Codec; 12] = [
+        Codec::Base64(Base64Format::Standard),
+        Codec::Base64(Base64Format::StandardNoPad),
+        Codec::Base64(Base64Format::UrlSafe),
+        Codec::Base64(Base64Format::UrlSafePad),
+        Codec::Base32,
+        Codec::Base32NoPad,
+        Codec::Base58,
+        Codec::Hex,
+        Codec::HexUpper,
+        Codec::Ascii85,
+        Codec::Z85,
+        // 解码时 base64-auto 可以读取任何一种 base64 变体
+        Codec::Base64(Base64Format::Auto),
+    ];
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rcli-codec-{}-{}", std::process::id(), name))
+    }
+
+    fn encode(codec: Codec, data: &[u8], name: &str) -> Result<String> {
+        let input = temp_file(&format!("{}.in", name));
+        let output = temp_file(&format!("{}.out", name));
+        fs::write(&input, data)?;
+        let result = process_codec(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            codec,
+            false,
+        );
+        let text = result.and_then(|_| Ok(fs::read_to_string(&output)?));
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+        text
+    }
+
+    fn decode(codec: Codec, text: &str, name: &str) -> Result<Vec<u8>> {
+        let input = temp_file(&format!("{}.txt", name));
+        let output = temp_file(&format!("{}.bin", name));
+        fs::write(&input, text)?;
+        let result = process_codec(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            codec,
+            true,
+        );
+        let data = result.and_then(|_| Ok(fs::read(&output)?));
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+        data
+    }
+
+    #[test]
+    fn every_codec_round_trips() {
+        let random = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let inputs: [&[u8]; 6] = [
+            b"",
+            b"\x01",
+            b"\xff\x00",
+            b"abc",
+            b"\0\0\0\0abcd\0\0\0\0",
+            &random,
+        ];
+        for codec in CODECS {
+            let encoder = match codec {
+                Codec::Base64(Base64Format::Auto) => Codec::Base64(Base64Format::UrlSafe),
+                codec => codec,
+            };
+            for (i, data) in inputs.iter().enumerate() {
+                // Z85 只接受 4 字节的整数倍
+                if let Codec::Z85 = codec {
+                    if data.len() % 4 != 0 {
+                        continue;
+                    }
+                }
+                let name = format!("{}-{}", codec, i);
+                let text = encode(encoder, data, &name).unwrap();
+                assert!(text.ends_with('\n'), "{}: {:?}", codec, text);
+                let decoded = decode(codec, &text, &name).unwrap();
+                assert_eq!(&decoded, data, "{} round trip of input {}", codec, i);
+            }
+        }
+    }
+
+    #[test]
+    fn auto_decodes_wrapped_padded_and_unpadded_base64() {
+        let data = (0..=255u8).collect::<Vec<_>>();
+        let auto = Codec::Base64(Base64Format::Auto);
+        for format in [
+            Base64Format::Standard,
+            Base64Format::StandardNoPad,
+            Base64Format::UrlSafe,
+            Base64Format::UrlSafePad,
+        ] {
+            let name = format!("auto-{}", format);
+            let text = encode(Codec::Base64(format), &data, &name).unwrap();
+            let wrapped = text
+                .as_bytes()
+                .chunks(76)
+                .map(|line| std::str::from_utf8(line).unwrap())
+                .collect::<Vec<_>>()
+                .join("\r\n");
+            assert_eq!(decode(auto, &wrapped, &name).unwrap(), data, "{}", format);
+        }
+    }
+
+    #[test]
+    fn base32_decodes_with_or_without_padding() {
+        // 16 字节的 TOTP 密钥，不带 padding 时为 26 个字符
+        let key = (1..=16u8).collect::<Vec<_>>();
+        let unpadded = encode(Codec::Base32NoPad, &key, "nopad").unwrap();
+        let padded = encode(Codec::Base32, &key, "pad").unwrap();
+        assert_eq!(unpadded.trim_end().len(), 26);
+        assert_eq!(padded.trim_end().len(), 32);
+        for codec in [Codec::Base32, Codec::Base32NoPad] {
+            assert_eq!(decode(codec, &unpadded, "nopad").unwrap(), key);
+            assert_eq!(decode(codec, &padded, "pad").unwrap(), key);
+        }
+        assert!(decode(Codec::Base32, "MZXW6Y==", "bad-length").is_err());
+    }
+
+    #[test]
+    fn z85_matches_rfc_32() {
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(encode_z85(&data).unwrap(), "HelloWorld");
+        assert_eq!(decode_z85("HelloWorld").unwrap(), data);
+    }
+
+    #[test]
+    fn ascii85_uses_z_for_zero_groups_and_keeps_partial_tails() {
+        assert_eq!(encode_ascii85(&[0; 4]), "z");
+        assert_eq!(encode_ascii85(&[0; 3]), "!!!!");
+        assert_eq!(decode_ascii85("z").unwrap(), [0; 4]);
+        assert_eq!(encode_ascii85(b"hello world"), "BOu!rD]j7BEbo7");
+        assert_eq!(
+            decode_ascii85("<~BOu!rD]j7BEbo7~>").unwrap(),
+            b"hello world"
+        );
+        assert_eq!(
+            encode_ascii85(b"hello world\0\0\0\0x"),
+            "BOu!rD]j7BEbo7d!!!\"D"
+        );
+    }
+
+    #[test]
+    fn invalid_input_is_an_error() {
+        assert!(encode_z85(b"abc").is_err());
+        assert!(decode_z85("HelloWor").is_err());
+        assert!(decode_z85("Hello\"orld").is_err());
+        assert!(decode_ascii85("ab!z").is_err());
+        assert!(decode_ascii85("BOu!rD]j7BEbo7d!!!\"Dv").is_err());
+        assert!(decode(Codec::Hex, "abc", "odd-hex").is_err());
+        assert!(decode(Codec::Base32, "NBSWY3D1", "bad-base32").is_err());
+    }
+
+    #[test]
+    fn auto_rejects_mixed_base64_alphabets() {
+        let input = temp_file("mixed.txt");
+        let output = temp_file("mixed.bin");
+        fs::write(&input, "ab+_").unwrap();
+        let result = process_decode(
+            input.to_str().unwrap(),
+            output.to_str().unwrap(),
+            Base64Format::Auto,
+        );
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("mixes"), "{}", error);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{get_reader, get_writer, OutputFormat};
+use anyhow::{anyhow, Result};
+use csv::WriterBuilder;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Read},
+};
+
+pub fn process_convert(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    delimiter: u8,
+    toml_key: Option<&str>,
+) -> Result<()> {
+    let reader = get_reader(input)?;
+    let records = read_records(reader, format, toml_key)?;
+
+    // header 为所有记录 key 的并集，按首次出现的顺序排列
+    let mut headers = Vec::new();
+    let mut seen = HashSet::new();
+    let mut rows = Vec::with_capacity(records.len());
+    for record in records {
+        let mut row = Vec::new();
+        flatten("", record, &mut row);
+        for (key, _) in &row {
+            if seen.insert(key.clone()) {
+                headers.push(key.clone());
+            }
+        }
+        rows.push(row.into_iter().collect::<HashMap<_, _>>());
+    }
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(get_writer(output)?);
+    writer.write_record(&headers)?;
+    for mut row in rows {
+        let record = headers.iter().map(|h| row.remove(h).unwrap_or_default());
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_records(
+    reader: Box<dyn Read>,
+    format: OutputFormat,
+    toml_key: Option<&str>,
+) -> Result<Vec<Value>> {
+    let records = read_values(reader, format, toml_key)?;
+    // 数组、ndjson 行和 yaml 文档的每一项都必须是对象，否则会写成空列名
+    if let Some(i) = records.iter().position(|record| !record.is_object()) {
+        return Err(anyhow!(
+            "Expect an array of objects, record {} is {}",
+            i + 1,
+            records[i]
+        ));
+    }
+    Ok(records)
+}
+
+fn read_values(
+    mut reader: Box<dyn Read>,
+    format: OutputFormat,
+    toml_key: Option<&str>,
+) -> Result<Vec<Value>> {
+    let value = match format {
+        OutputFormat::Ndjson => {
+            let mut records = Vec::new();
+            for line in BufReader::new(reader).lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    records.push(serde_json::from_str(&line)?);
+                }
+            }
+            return Ok(records);
+        }
+        OutputFormat::Xlsx | OutputFormat::Parquet => {
+            return Err(anyhow!("{} is not supported as input", format));
+        }
+        OutputFormat::Json => serde_json::from_reader(reader)?,
+        OutputFormat::Yaml => {
+            let mut docs = serde_yaml::Deserializer::from_reader(reader)
+                .map(Value::deserialize)
+                .collect::<Result<Vec<_>, _>>()?;
+            // 多文档 yaml（如 `rcli csv --stream` 的输出）每个文档是一条记录
+            if docs.len() != 1 {
+                return Ok(docs);
+            }
+            docs.remove(0)
+        }
+        OutputFormat::Toml => {
+            let mut buf = String::new();
+            reader.read_to_string(&mut buf)?;
+            let mut table = buf.parse::<toml::Table>()?;
+            let key = match toml_key {
+                Some(key) => key.to_string(),
+                None => find_array_key(&table)?,
+            };
+            let tables = table
+                .remove(&key)
+                .ok_or_else(|| anyhow!("Key {:?} not found in toml input", key))?;
+            toml_to_json(tables)
+        }
+    };
+    match value {
+        Value::Array(records) => Ok(records),
+        Value::Object(_) => Ok(vec![value]),
+        _ => Err(anyhow!("Expect an array of objects")),
+    }
+}
+
+// 未指定 --toml-key 时，取唯一的数组字段
+fn find_array_key(table: &toml::Table) -> Result<String> {
+    let mut keys = table.iter().filter(|(_, v)| v.is_array()).map(|(k, _)| k);
+    match (keys.next(), keys.next()) {
+        (Some(key), None) => Ok(key.clone()),
+        _ => Err(anyhow!("Cannot find the array of tables, use --toml-key")),
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(v) => Value::String(v),
+        toml::Value::Integer(v) => Value::Number(v.into()),
+        toml::Value::Float(v) => serde_json::Number::from_f64(v).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(v) => Value::Bool(v),
+        toml::Value::Datetime(v) => Value::String(v.to_string()),
+        toml::Value::Array(v) => Value::Array(v.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(v) => Value::Object(
+            v.into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+/// Flatten nested objects into dotted keys, e.g. `{"a": {"b": 1}}` becomes `a.b = 1`.
+/// Arrays are kept as json text.
+fn flatten(prefix: &str, value: Value, row: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, row);
+            }
+        }
+        Value::Null => {
+            row.push((prefix.into(), String::new()));
+        }
+        Value::String(v) => {
+            row.push((prefix.into(), v));
+        }
+        v => {
+            row.push((prefix.into(), v.to_string()));
+        }
+    }
+}
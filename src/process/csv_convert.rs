@@ -1,38 +1,205 @@
+use super::{
+    csv_export::{to_parquet, to_xlsx},
+    csv_query::Query,
+    csv_types::{row_to_json, row_to_toml, Cell, ColumnTypes},
+};
+use crate::{
+    get_reader, get_writer, CsvQueryOpts, CsvReaderOpts, CsvTypeOpts, CsvWriteOpts, DecodeReader,
+    OutputFormat,
+};
 use anyhow::Result;
-use csv::Reader;
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::fs;
-use crate::OutputFormat;
-
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-pub struct Player {
-    name: String,
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
+use csv::{Reader, ReaderBuilder, StringRecord};
+use encoding_rs::UTF_8;
+use std::io::{BufWriter, Read, Write};
+
+pub type CsvReader = Reader<DecodeReader<Box<dyn Read>>>;
+
+pub fn process_csv(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    opts: &CsvReaderOpts,
+    types: &CsvTypeOpts,
+    query: &CsvQueryOpts,
+    writer: &CsvWriteOpts,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, opts)?;
+    let records = reader.into_records().map(|r| r.map_err(Into::into));
+    process_records(output, &headers, records, format, types, query, writer)
+}
+
+/// Type, filter, sort and write records, shared by convert, join and concat.
+/// Rows are read one at a time unless `--sort-by` is given.
+pub fn process_records(
+    output: &str,
+    headers: &StringRecord,
+    records: impl Iterator<Item = Result<StringRecord>>,
+    format: OutputFormat,
+    types: &CsvTypeOpts,
+    query: &CsvQueryOpts,
+    writer: &CsvWriteOpts,
+) -> Result<()> {
+    let types = ColumnTypes::new(headers, types)?;
+    let query = Query::new(headers, query)?;
+
+    let rows = records.filter_map(
+        |record| match record.and_then(|record| types.parse(&record)) {
+            Ok(row) if !query.matches(&row) => None,
+            row => Some(row),
+        },
+    );
+    // --sort-by 需要先读完全部记录
+    let rows: Box<dyn Iterator<Item = Result<Vec<Cell>>>> = if query.is_sorted() {
+        let mut rows = rows.collect::<Result<Vec<_>>>()?;
+        query.sort(&mut rows);
+        Box::new(rows.into_iter().map(Ok))
+    } else {
+        Box::new(rows)
+    };
+    let rows = rows
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|row| row.map(|row| query.project(row)));
+
+    write_records(output, &query.headers(headers), rows, format, writer)
 }
 
-pub fn process_csv(input: &str, output: &str, format: OutputFormat) -> Result<()> {
-    let mut reader = Reader::from_path(input)?;
-    let mut ret = Vec::with_capacity(128);
-    let headers = reader.headers()?.clone();
-    for result in reader.records() {
-        let record = result?;
-        let json_value = headers.iter().zip(record.iter()).collect::<Value>();
-        ret.push(json_value);
+/// Serialize rows to `output`, one at a time when streaming (always for ndjson).
+pub fn write_records(
+    output: &str,
+    headers: &StringRecord,
+    rows: impl Iterator<Item = Result<Vec<Cell>>>,
+    format: OutputFormat,
+    opts: &CsvWriteOpts,
+) -> Result<()> {
+    // xlsx 与 parquet 需要完整数据才能写出，忽略 --stream
+    let binary = matches!(format, OutputFormat::Xlsx | OutputFormat::Parquet);
+    if (opts.stream && !binary) || matches!(format, OutputFormat::Ndjson) {
+        let out = BufWriter::new(get_writer(output)?);
+        let mut writer = RecordWriter::new(out, format, &opts.toml_key);
+        for row in rows {
+            writer.write(headers, row?)?;
+        }
+        return writer.finish();
     }
 
-   let content =  match format {
-        OutputFormat::Json =>serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
-       _ => todo!(),
+    let ret = rows.collect::<Result<Vec<_>>>()?;
+    let content = match format {
+        OutputFormat::Xlsx => to_xlsx(headers, ret)?,
+        OutputFormat::Parquet => to_parquet(headers, ret)?,
+        OutputFormat::Json => {
+            let ret = ret
+                .into_iter()
+                .map(|row| row_to_json(headers, row))
+                .collect::<Vec<_>>();
+            let mut content = serde_json::to_string_pretty(&ret)?;
+            content.push('\n');
+            content.into_bytes()
+        }
+        OutputFormat::Yaml => {
+            let ret = ret
+                .into_iter()
+                .map(|row| row_to_json(headers, row))
+                .collect::<Vec<_>>();
+            serde_yaml::to_string(&ret)?.into_bytes()
+        }
+        OutputFormat::Toml => {
+            // toml has no top-level array, so records are written as `[[toml_key]]` tables
+            let ret = ret
+                .into_iter()
+                .map(|row| row_to_toml(headers, row))
+                .collect::<Vec<_>>();
+            let mut doc = toml::Table::new();
+            doc.insert(opts.toml_key.clone(), ret.into());
+            toml::to_string(&doc)?.into_bytes()
+        }
+        OutputFormat::Ndjson => unreachable!("ndjson is always streamed"),
     };
-    
-    fs::write(output, content)?;
+
+    let mut out = get_writer(output)?;
+    out.write_all(&content)?;
+    out.flush()?;
     Ok(())
 }
+
+/// Open `input` with the given dialect and resolve the column names:
+/// `--columns` first, then the header row, then `col1`, `col2`, ...
+pub fn open_csv(input: &str, opts: &CsvReaderOpts) -> Result<(CsvReader, StringRecord)> {
+    let encoding = opts.encoding.unwrap_or(UTF_8);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(opts.delimiter)
+        .quote(opts.quote)
+        .escape(opts.escape)
+        .double_quote(opts.escape.is_none())
+        .has_headers(opts.header)
+        .from_reader(DecodeReader::new(get_reader(input)?, encoding));
+    // without a header row, headers() peeks at the first record without consuming it
+    let first = reader.headers()?.clone();
+    let headers = if !opts.columns.is_empty() {
+        opts.columns.iter().collect()
+    } else if opts.header {
+        first
+    } else {
+        (1..=first.len()).map(|i| format!("col{}", i)).collect()
+    };
+    Ok((reader, headers))
+}
+
+/// Writes records one at a time as they are read:
+/// a json array, ndjson lines, yaml documents or toml `[[toml_key]]` tables.
+struct RecordWriter<'a, W: Write> {
+    out: W,
+    format: OutputFormat,
+    toml_key: &'a str,
+    count: usize,
+}
+
+impl<'a, W: Write> RecordWriter<'a, W> {
+    fn new(out: W, format: OutputFormat, toml_key: &'a str) -> Self {
+        Self {
+            out,
+            format,
+            toml_key,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, headers: &StringRecord, row: Vec<Cell>) -> Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                let sep = if self.count == 0 { "[\n" } else { ",\n" };
+                self.out.write_all(sep.as_bytes())?;
+                serde_json::to_writer(&mut self.out, &row_to_json(headers, row))?;
+            }
+            OutputFormat::Ndjson => {
+                serde_json::to_writer(&mut self.out, &row_to_json(headers, row))?;
+                self.out.write_all(b"\n")?;
+            }
+            OutputFormat::Yaml => {
+                self.out.write_all(b"---\n")?;
+                serde_yaml::to_writer(&mut self.out, &row_to_json(headers, row))?;
+            }
+            OutputFormat::Xlsx | OutputFormat::Parquet => {
+                unreachable!("binary formats are never streamed")
+            }
+            OutputFormat::Toml => {
+                if self.count > 0 {
+                    self.out.write_all(b"\n")?;
+                }
+                let mut doc = toml::Table::new();
+                doc.insert(self.toml_key.into(), vec![row_to_toml(headers, row)].into());
+                self.out.write_all(toml::to_string(&doc)?.as_bytes())?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if let OutputFormat::Json = self.format {
+            let end = if self.count == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(end.as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
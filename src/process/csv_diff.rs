@@ -0,0 +1,199 @@
+use super::csv_convert::open_csv;
+use crate::{get_writer, CsvReaderOpts, OutputFormat};
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct CsvDiff {
+    pub key: String,
+    pub columns_added: Vec<String>,
+    pub columns_removed: Vec<String>,
+    pub added: Vec<Map<String, Value>>,
+    pub removed: Vec<Map<String, Value>>,
+    pub changed: Vec<RowChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowChange {
+    pub key: String,
+    pub changes: Vec<CellChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CellChange {
+    pub column: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Compare two csv files row by row on the `key` column. Cells are compared as
+/// text, only columns present in both files are checked for changes.
+pub fn process_csv_diff(
+    old: &str,
+    new: &str,
+    key: &str,
+    output: &str,
+    format: Option<OutputFormat>,
+    opts: &CsvReaderOpts,
+) -> Result<()> {
+    let (old_headers, old_rows) = read_keyed(old, key, opts)?;
+    let (new_headers, new_rows) = read_keyed(new, key, opts)?;
+    let position = |headers: &StringRecord, name: &str| headers.iter().position(|h| h == name);
+
+    let mut diff = CsvDiff {
+        key: key.into(),
+        columns_added: new_headers
+            .iter()
+            .filter(|h| position(&old_headers, h).is_none())
+            .map(String::from)
+            .collect(),
+        columns_removed: old_headers
+            .iter()
+            .filter(|h| position(&new_headers, h).is_none())
+            .map(String::from)
+            .collect(),
+        ..Default::default()
+    };
+    // 两个文件共有的列：(列名, 旧索引, 新索引)
+    let common = new_headers
+        .iter()
+        .filter_map(|h| Some((h, position(&old_headers, h)?, position(&new_headers, h)?)))
+        .collect::<Vec<_>>();
+
+    let old_index = old_rows
+        .iter()
+        .enumerate()
+        .map(|(i, (key, _))| (key.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let new_index = new_rows
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .collect::<HashSet<_>>();
+
+    for (key, record) in &old_rows {
+        if !new_index.contains(key.as_str()) {
+            diff.removed.push(to_object(&old_headers, record));
+        }
+    }
+    for (key, record) in &new_rows {
+        let Some(&i) = old_index.get(key.as_str()) else {
+            diff.added.push(to_object(&new_headers, record));
+            continue;
+        };
+        let old = &old_rows[i].1;
+        let changes = common
+            .iter()
+            .filter_map(|&(column, o, n)| {
+                let old = old.get(o).unwrap_or_default();
+                let new = record.get(n).unwrap_or_default();
+                (old != new).then(|| CellChange {
+                    column: column.into(),
+                    old: old.into(),
+                    new: new.into(),
+                })
+            })
+            .collect::<Vec<_>>();
+        if !changes.is_empty() {
+            diff.changed.push(RowChange {
+                key: key.clone(),
+                changes,
+            });
+        }
+    }
+
+    let content = match format {
+        None => diff.to_string(),
+        Some(OutputFormat::Json) => serde_json::to_string_pretty(&diff)? + "\n",
+        Some(OutputFormat::Yaml) => serde_yaml::to_string(&diff)?,
+        Some(OutputFormat::Toml) => toml::to_string(&diff)?,
+        Some(format) => return Err(anyhow!("Diff cannot be written as {}", format)),
+    };
+    let mut out = get_writer(output)?;
+    out.write_all(content.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+// 读入全部记录，key 不允许重复
+fn read_keyed(
+    input: &str,
+    key: &str,
+    opts: &CsvReaderOpts,
+) -> Result<(StringRecord, Vec<(String, StringRecord)>)> {
+    let (reader, headers) = open_csv(input, opts)?;
+    let index = headers
+        .iter()
+        .position(|h| h == key)
+        .ok_or_else(|| anyhow!("Column {:?} not found in {}", key, input))?;
+    let mut lines = HashMap::new();
+    let mut rows = Vec::new();
+    for record in reader.into_records() {
+        let record = record?;
+        let value = record.get(index).unwrap_or_default().to_string();
+        let line = record.position().map_or(0, |p| p.line());
+        if let Some(first) = lines.insert(value.clone(), line) {
+            return Err(anyhow!(
+                "{}: duplicate key {:?} at line {} and {}",
+                input,
+                value,
+                first,
+                line
+            ));
+        }
+        rows.push((value, record));
+    }
+    Ok((headers, rows))
+}
+
+fn to_object(headers: &StringRecord, record: &StringRecord) -> Map<String, Value> {
+    headers
+        .iter()
+        .zip(record.iter())
+        .map(|(h, v)| (h.to_string(), Value::String(v.into())))
+        .collect()
+}
+
+impl fmt::Display for CsvDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = |row: &Map<String, Value>| match row.get(&self.key) {
+            Some(Value::String(s)) => s.clone(),
+            _ => String::new(),
+        };
+        if !self.columns_added.is_empty() {
+            writeln!(f, "columns added: {}", self.columns_added.join(", "))?;
+        }
+        if !self.columns_removed.is_empty() {
+            writeln!(f, "columns removed: {}", self.columns_removed.join(", "))?;
+        }
+        for row in &self.removed {
+            writeln!(f, "- {}={}", self.key, key(row))?;
+        }
+        for row in &self.added {
+            writeln!(f, "+ {}={}", self.key, key(row))?;
+        }
+        for row in &self.changed {
+            writeln!(f, "~ {}={}", self.key, row.key)?;
+            for change in &row.changes {
+                writeln!(
+                    f,
+                    "    {}: {:?} -> {:?}",
+                    change.column, change.old, change.new
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
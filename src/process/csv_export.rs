@@ -0,0 +1,174 @@
+use super::csv_types::{Cell, Kind};
+use anyhow::Result;
+use chrono::DateTime;
+use csv::StringRecord;
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    column::writer::ColumnWriter,
+    data_type::ByteArray,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    format::MilliSeconds,
+    schema::types::Type,
+};
+use rust_xlsxwriter::{Format, Workbook};
+use std::sync::Arc;
+
+/// A single worksheet with a bold, frozen header row. Numbers, bools and dates
+/// are written as typed cells, empty cells are left blank.
+pub fn to_xlsx(headers: &StringRecord, rows: Vec<Vec<Cell>>) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let bold = Format::new().set_bold();
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+    let datetime = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, header, &bold)?;
+    }
+    for (row, cells) in rows.into_iter().enumerate() {
+        let row = row as u32 + 1;
+        for (col, cell) in cells.into_iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Null => continue,
+                Cell::Bool(v) => sheet.write_boolean(row, col, v)?,
+                Cell::Int(v) => sheet.write_number(row, col, v as f64)?,
+                Cell::Float(v) => sheet.write_number(row, col, v)?,
+                Cell::Date(v) => sheet.write_datetime_with_format(row, col, v, &date)?,
+                Cell::DateTime(v) => sheet.write_datetime_with_format(row, col, v, &datetime)?,
+                Cell::String(v) => sheet.write_string(row, col, v)?,
+            };
+        }
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofit();
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// One row group, every column is optional and typed by the kinds of its cells:
+/// int64, double, boolean, date, timestamp(millis) or utf8 when mixed.
+pub fn to_parquet(headers: &StringRecord, rows: Vec<Vec<Cell>>) -> Result<Vec<u8>> {
+    let kinds = headers
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            rows.iter()
+                .filter_map(|row| row.get(i).and_then(Cell::kind))
+                .reduce(Kind::merge)
+                .unwrap_or(Kind::String)
+        })
+        .collect::<Vec<_>>();
+    let fields = headers
+        .iter()
+        .zip(&kinds)
+        .map(|(name, kind)| parquet_field(name, *kind).map(Arc::new))
+        .collect::<Result<Vec<_>>>()?;
+    let schema = Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(props))?;
+    let mut group = writer.next_row_group()?;
+    let mut i = 0;
+    while let Some(mut column) = group.next_column()? {
+        let cells = rows.iter().map(|row| row.get(i).unwrap_or(&Cell::Null));
+        // 定义级别：1 为有值，0 为 null
+        let levels = cells
+            .clone()
+            .map(|cell| i16::from(*cell != Cell::Null))
+            .collect::<Vec<_>>();
+        let cells = cells.filter(|cell| **cell != Cell::Null);
+        match column.untyped() {
+            ColumnWriter::Int64ColumnWriter(w) if kinds[i] == Kind::Int => {
+                let values = cells.map(as_i64).collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnWriter::Int64ColumnWriter(w) => {
+                let values = cells.map(as_millis).collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnWriter::DoubleColumnWriter(w) => {
+                let values = cells.map(as_f64).collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnWriter::BoolColumnWriter(w) => {
+                let values = cells
+                    .map(|cell| matches!(cell, Cell::Bool(true)))
+                    .collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnWriter::Int32ColumnWriter(w) => {
+                let values = cells.map(as_days).collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnWriter::ByteArrayColumnWriter(w) => {
+                let values = cells
+                    .map(|cell| ByteArray::from(cell.to_string().into_bytes()))
+                    .collect::<Vec<_>>();
+                w.write_batch(&values, Some(&levels), None)?;
+            }
+            _ => unreachable!("no such column type in the schema"),
+        }
+        column.close()?;
+        i += 1;
+    }
+    group.close()?;
+    Ok(writer.into_inner()?)
+}
+
+fn parquet_field(name: &str, kind: Kind) -> Result<Type> {
+    let (physical, logical) = match kind {
+        Kind::Int => (PhysicalType::INT64, None),
+        Kind::Float => (PhysicalType::DOUBLE, None),
+        Kind::Bool => (PhysicalType::BOOLEAN, None),
+        Kind::Date => (PhysicalType::INT32, Some(LogicalType::Date)),
+        Kind::DateTime => (
+            PhysicalType::INT64,
+            Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: false,
+                unit: TimeUnit::MILLIS(MilliSeconds {}),
+            }),
+        ),
+        Kind::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+    };
+    Ok(Type::primitive_type_builder(name, physical)
+        .with_repetition(Repetition::OPTIONAL)
+        .with_logical_type(logical)
+        .build()?)
+}
+
+fn as_i64(cell: &Cell) -> i64 {
+    match cell {
+        Cell::Int(v) => *v,
+        _ => 0,
+    }
+}
+
+fn as_f64(cell: &Cell) -> f64 {
+    match cell {
+        Cell::Int(v) => *v as f64,
+        Cell::Float(v) => *v,
+        _ => f64::NAN,
+    }
+}
+
+fn as_days(cell: &Cell) -> i32 {
+    match cell {
+        Cell::Date(v) => v
+            .signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+            .num_days() as i32,
+        _ => 0,
+    }
+}
+
+// date 与 datetime 混合的列统一为 timestamp，date 取当天零点
+fn as_millis(cell: &Cell) -> i64 {
+    match cell {
+        Cell::Date(v) => v.and_time(Default::default()).and_utc().timestamp_millis(),
+        Cell::DateTime(v) => v.and_utc().timestamp_millis(),
+        _ => 0,
+    }
+}
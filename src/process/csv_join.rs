@@ -0,0 +1,216 @@
+use super::csv_convert::open_csv;
+use crate::{CsvReaderOpts, DecodeReader, JoinHow};
+use anyhow::{anyhow, Result};
+use csv::{StringRecord, StringRecordsIntoIter};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::Read,
+};
+
+pub type Records = Box<dyn Iterator<Item = Result<StringRecord>>>;
+
+/// Merge `right` into `left` on the key column. The right file is loaded into
+/// memory, the left one is streamed. Right columns whose names clash with the
+/// left ones get `suffix` appended, the right key column is dropped.
+pub fn process_csv_join(
+    left: &str,
+    right: &str,
+    reader: &CsvReaderOpts,
+    on: &str,
+    how: JoinHow,
+    suffix: &str,
+) -> Result<(StringRecord, Records)> {
+    let (left_reader, left_headers) = open_csv(left, reader)?;
+    let (right_reader, right_headers) = open_csv(right, reader)?;
+    let key_index = |headers: &StringRecord, file: &str| {
+        headers
+            .iter()
+            .position(|h| h == on)
+            .ok_or_else(|| anyhow!("Column {:?} not found in {}", on, file))
+    };
+    let left_key = key_index(&left_headers, left)?;
+    let right_key = key_index(&right_headers, right)?;
+
+    let mut headers = left_headers.clone();
+    let mut names = left_headers
+        .iter()
+        .map(String::from)
+        .collect::<HashSet<_>>();
+    for (i, name) in right_headers.iter().enumerate() {
+        if i == right_key {
+            continue;
+        }
+        let mut name = name.to_string();
+        while names.contains(&name) {
+            name.push_str(suffix);
+        }
+        headers.push_field(&name);
+        names.insert(name);
+    }
+
+    let mut rows = Vec::new();
+    let mut index = HashMap::<String, Vec<usize>>::new();
+    for record in right_reader.into_records() {
+        let record = record?;
+        // 空 key 视为缺失，不参与匹配
+        let key = record.get(right_key).unwrap_or_default();
+        if !key.is_empty() {
+            index.entry(key.into()).or_default().push(rows.len());
+        }
+        rows.push(record);
+    }
+
+    let join = JoinRows {
+        left: left_reader.into_records(),
+        left_width: left_headers.len(),
+        left_key,
+        right_key,
+        right_width: right_headers.len(),
+        matched: vec![false; rows.len()],
+        right: rows,
+        index,
+        how,
+        pending: VecDeque::new(),
+        unmatched: 0,
+    };
+    Ok((headers, Box::new(join)))
+}
+
+/// Stack files with the same columns, later files may order them differently.
+pub fn process_csv_concat(
+    inputs: &[String],
+    reader: &CsvReaderOpts,
+) -> Result<(StringRecord, Records)> {
+    let mut headers = None;
+    let mut files = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let (csv, current) = open_csv(input, reader)?;
+        let headers = headers.get_or_insert_with(|| current.clone());
+        let order = column_order(headers, &current).map_err(|e| anyhow!("{}: {}", input, e))?;
+        files.push((csv, order));
+    }
+
+    let records = files.into_iter().flat_map(|(csv, order)| {
+        csv.into_records().map(move |record| {
+            let record = record?;
+            Ok(match &order {
+                Some(order) => {
+                    let mut reordered = order
+                        .iter()
+                        .map(|&i| record.get(i).unwrap_or_default())
+                        .collect::<StringRecord>();
+                    reordered.set_position(record.position().cloned());
+                    reordered
+                }
+                None => record,
+            })
+        })
+    });
+    Ok((headers.unwrap_or_default(), Box::new(records)))
+}
+
+// None 表示列顺序相同，无需调整
+fn column_order(headers: &StringRecord, current: &StringRecord) -> Result<Option<Vec<usize>>> {
+    if headers == current {
+        return Ok(None);
+    }
+    let missing = headers
+        .iter()
+        .filter(|h| !current.iter().any(|c| c == *h))
+        .collect::<Vec<_>>();
+    let extra = current
+        .iter()
+        .filter(|c| !headers.iter().any(|h| h == *c))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() || !extra.is_empty() || headers.len() != current.len() {
+        return Err(anyhow!(
+            "columns do not match the first file, missing {:?}, extra {:?}",
+            missing,
+            extra
+        ));
+    }
+    let order = headers
+        .iter()
+        .map(|h| current.iter().position(|c| c == h).unwrap())
+        .collect();
+    Ok(Some(order))
+}
+
+struct JoinRows {
+    left: StringRecordsIntoIter<DecodeReader<Box<dyn Read>>>,
+    left_width: usize,
+    left_key: usize,
+    right: Vec<StringRecord>,
+    right_key: usize,
+    right_width: usize,
+    index: HashMap<String, Vec<usize>>,
+    matched: Vec<bool>,
+    how: JoinHow,
+    pending: VecDeque<StringRecord>,
+    // outer join 时左表读完后，从这里开始输出未匹配的右表记录
+    unmatched: usize,
+}
+
+impl JoinRows {
+    fn merge(&self, left: Option<&StringRecord>, right: Option<&StringRecord>) -> StringRecord {
+        let mut record = StringRecord::new();
+        match left {
+            Some(left) => {
+                record.extend((0..self.left_width).map(|i| left.get(i).unwrap_or_default()))
+            }
+            None => {
+                // 右表独有的记录，key 写在左表的 key 列
+                let key = right
+                    .and_then(|r| r.get(self.right_key))
+                    .unwrap_or_default();
+                record.extend(
+                    (0..self.left_width).map(|i| if i == self.left_key { key } else { "" }),
+                );
+            }
+        }
+        let cells = (0..self.right_width)
+            .filter(|&i| i != self.right_key)
+            .map(|i| right.and_then(|r| r.get(i)).unwrap_or_default());
+        record.extend(cells);
+        record.set_position(left.or(right).and_then(|r| r.position()).cloned());
+        record
+    }
+}
+
+impl Iterator for JoinRows {
+    type Item = Result<StringRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.pending.pop_front() {
+                return Some(Ok(record));
+            }
+            match self.left.next() {
+                Some(Err(e)) => return Some(Err(e.into())),
+                Some(Ok(left)) => {
+                    let key = left.get(self.left_key).unwrap_or_default();
+                    let matches = self.index.get(key).cloned().unwrap_or_default();
+                    if matches.is_empty() && self.how != JoinHow::Inner {
+                        return Some(Ok(self.merge(Some(&left), None)));
+                    }
+                    for i in matches {
+                        self.matched[i] = true;
+                        let record = self.merge(Some(&left), Some(&self.right[i]));
+                        self.pending.push_back(record);
+                    }
+                }
+                None if self.how == JoinHow::Outer => {
+                    while self.unmatched < self.right.len() {
+                        let i = self.unmatched;
+                        self.unmatched += 1;
+                        if !self.matched[i] {
+                            return Some(Ok(self.merge(None, Some(&self.right[i]))));
+                        }
+                    }
+                    return None;
+                }
+                None => return None,
+            }
+        }
+    }
+}
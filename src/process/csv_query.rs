@@ -0,0 +1,105 @@
+use super::csv_types::Cell;
+use crate::{CompareOp, Condition, CsvQueryOpts};
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+use std::cmp::Ordering;
+
+/// `--select/--where/--sort-by/--limit` resolved against the header row.
+pub struct Query {
+    select: Option<Vec<usize>>,
+    filters: Vec<(usize, CompareOp, String)>,
+    sort_by: Option<(usize, bool)>,
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    pub fn new(headers: &StringRecord, opts: &CsvQueryOpts) -> Result<Self> {
+        let index = |column: &str| {
+            headers
+                .iter()
+                .position(|h| h == column)
+                .ok_or_else(|| anyhow!("Unknown column {:?}", column))
+        };
+        let select = if opts.select.is_empty() {
+            None
+        } else {
+            Some(
+                opts.select
+                    .iter()
+                    .map(|c| index(c))
+                    .collect::<Result<_>>()?,
+            )
+        };
+        let filters = opts
+            .filters
+            .iter()
+            .flat_map(|c| &c.0)
+            .map(|Condition { column, op, value }| Ok((index(column)?, *op, value.clone())))
+            .collect::<Result<_>>()?;
+        let sort_by = match &opts.sort_by {
+            Some(column) => Some((index(column)?, opts.desc)),
+            None => None,
+        };
+        Ok(Self {
+            select,
+            filters,
+            sort_by,
+            limit: opts.limit,
+        })
+    }
+
+    pub fn headers(&self, headers: &StringRecord) -> StringRecord {
+        match &self.select {
+            Some(select) => select.iter().map(|&i| &headers[i]).collect(),
+            None => headers.clone(),
+        }
+    }
+
+    pub fn matches(&self, row: &[Cell]) -> bool {
+        self.filters.iter().all(|(i, op, value)| {
+            let cell = row.get(*i).unwrap_or(&Cell::Null);
+            // 字符串列按字面比较，其它列把条件值推断成同类型再比较
+            let value = match cell {
+                Cell::String(_) => Cell::String(value.clone()),
+                _ => Cell::infer(value),
+            };
+            let ord = cell.compare(&value);
+            match op {
+                CompareOp::Eq => ord == Ordering::Equal,
+                CompareOp::Ne => ord != Ordering::Equal,
+                CompareOp::Gt => ord == Ordering::Greater,
+                CompareOp::Ge => ord != Ordering::Less,
+                CompareOp::Lt => ord == Ordering::Less,
+                CompareOp::Le => ord != Ordering::Greater,
+            }
+        })
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.sort_by.is_some()
+    }
+
+    pub fn sort(&self, rows: &mut [Vec<Cell>]) {
+        if let Some((i, desc)) = self.sort_by {
+            let null = Cell::Null;
+            rows.sort_by(|a, b| {
+                let (a, b) = (a.get(i).unwrap_or(&null), b.get(i).unwrap_or(&null));
+                if desc {
+                    b.compare(a)
+                } else {
+                    a.compare(b)
+                }
+            });
+        }
+    }
+
+    pub fn project(&self, row: Vec<Cell>) -> Vec<Cell> {
+        match &self.select {
+            Some(select) => select
+                .iter()
+                .map(|&i| row.get(i).cloned().unwrap_or(Cell::Null))
+                .collect(),
+            None => row,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::{csv_convert::open_csv, csv_types::Cell};
+use crate::{CellType, CsvReaderOpts};
+use anyhow::{anyhow, Context, Result};
+use csv::StringRecord;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+};
+
+/// A declarative csv schema, written in yaml (or json):
+///
+/// ```yaml
+/// strict: true
+/// columns:
+///   - name: Kit Number
+///     type: int
+///     unique: true
+///   - name: Position
+///     enum: [Goalkeeper, Defender, Midfield, Forward]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    pub columns: Vec<ColumnSchema>,
+    // 为 true 时不允许出现 schema 中未声明的列
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    #[serde(default = "default_true")]
+    pub nullable: bool,
+    pub pattern: Option<String>,
+    #[serde(rename = "enum")]
+    pub values: Option<Vec<Value>>,
+    #[serde(default)]
+    pub unique: bool,
+}
+
+#[derive(Debug)]
+pub struct Violation {
+    pub line: u64,
+    // 从 1 开始，0 表示整行或表头
+    pub column: usize,
+    pub name: String,
+    pub message: String,
+}
+
+struct ColumnRule<'a> {
+    index: usize,
+    schema: &'a ColumnSchema,
+    ty: Option<CellType>,
+    pattern: Option<Regex>,
+    values: Option<HashSet<String>>,
+    // 值 -> 首次出现的行号
+    seen: HashMap<String, u64>,
+}
+
+/// Check every record against the schema, returns the row count and all violations.
+pub fn process_csv_validate(
+    input: &str,
+    schema: &str,
+    opts: &CsvReaderOpts,
+) -> Result<(usize, Vec<Violation>)> {
+    let content = fs::read_to_string(schema)?;
+    let schema: Schema =
+        serde_yaml::from_str(&content).with_context(|| format!("Invalid schema {}", schema))?;
+    let (mut reader, headers) = open_csv(input, opts)?;
+
+    let mut violations = Vec::new();
+    let header_line = if opts.header { 1 } else { 0 };
+    let mut rules = Vec::with_capacity(schema.columns.len());
+    for column in &schema.columns {
+        match headers.iter().position(|h| h == column.name) {
+            Some(index) => rules.push(ColumnRule::new(index, column)?),
+            None if column.required => violations.push(Violation {
+                line: header_line,
+                column: 0,
+                name: column.name.clone(),
+                message: "missing required column".into(),
+            }),
+            None => {}
+        }
+    }
+    if schema.strict {
+        for (index, name) in headers.iter().enumerate() {
+            if !schema.columns.iter().any(|c| c.name == name) {
+                violations.push(Violation {
+                    line: header_line,
+                    column: index + 1,
+                    name: name.into(),
+                    message: "column not declared in schema".into(),
+                });
+            }
+        }
+    }
+
+    let mut rows = 0;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        rows += 1;
+        let line = record.position().map_or(0, |p| p.line());
+        for rule in &mut rules {
+            let cell = record.get(rule.index).unwrap_or_default();
+            if let Err(message) = rule.check(cell, line) {
+                violations.push(Violation {
+                    line,
+                    column: rule.index + 1,
+                    name: rule.schema.name.clone(),
+                    message,
+                });
+            }
+        }
+    }
+    Ok((rows, violations))
+}
+
+impl<'a> ColumnRule<'a> {
+    fn new(index: usize, schema: &'a ColumnSchema) -> Result<Self> {
+        let ty = match &schema.ty {
+            Some(ty) => Some(
+                ty.parse()
+                    .with_context(|| format!("column {:?}", schema.name))?,
+            ),
+            None => None,
+        };
+        let pattern = match &schema.pattern {
+            Some(pattern) => Some(
+                Regex::new(pattern)
+                    .map_err(|e| anyhow!("column {:?}: invalid pattern: {}", schema.name, e))?,
+            ),
+            None => None,
+        };
+        // 枚举值可能写成数字或布尔，统一按文本比较
+        let values = schema.values.as_ref().map(|values| {
+            values
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                })
+                .collect()
+        });
+        Ok(Self {
+            index,
+            schema,
+            ty,
+            pattern,
+            values,
+            seen: HashMap::new(),
+        })
+    }
+
+    fn check(&mut self, cell: &str, line: u64) -> Result<(), String> {
+        if cell.is_empty() {
+            return if self.schema.nullable {
+                Ok(())
+            } else {
+                Err("value is required".into())
+            };
+        }
+        if let Some(ty) = &self.ty {
+            Cell::parse(cell, ty).map_err(|e| format!("expect {}: {}", ty, e))?;
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(cell) {
+                return Err(format!("{:?} does not match /{}/", cell, pattern));
+            }
+        }
+        if let Some(values) = &self.values {
+            if !values.contains(cell) {
+                return Err(format!("{:?} is not one of the allowed values", cell));
+            }
+        }
+        if self.schema.unique {
+            if let Some(first) = self.seen.get(cell) {
+                return Err(format!("{:?} duplicates line {}", cell, first));
+            }
+            self.seen.insert(cell.into(), line);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} ({}): {}",
+            self.line, self.column, self.name, self.message
+        )
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
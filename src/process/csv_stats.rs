@@ -0,0 +1,231 @@
+use super::{
+    csv_convert::open_csv,
+    csv_types::{Cell, ColumnTypes, Kind},
+};
+use crate::{get_writer, CsvReaderOpts, CsvTypeOpts, OutputFormat};
+use anyhow::{anyhow, Result};
+use csv::StringRecord;
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt, io::Write};
+
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    pub rows: u64,
+    pub columns: Vec<ColumnStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub nulls: u64,
+    pub distinct: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    pub longest: String,
+    pub top: Vec<ValueCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Default)]
+struct ColumnAcc {
+    kind: Option<Kind>,
+    nulls: u64,
+    counts: HashMap<String, u64>,
+    min: Option<Cell>,
+    max: Option<Cell>,
+    sum: f64,
+    numbers: u64,
+    longest: String,
+}
+
+/// Profile every column of `input`, then write a table (no format) or the given format.
+pub fn process_csv_stats(
+    input: &str,
+    output: &str,
+    format: Option<OutputFormat>,
+    opts: &CsvReaderOpts,
+    types: &CsvTypeOpts,
+    top: usize,
+) -> Result<()> {
+    let (mut reader, headers) = open_csv(input, opts)?;
+    let types = ColumnTypes::new(&headers, types)?;
+    let mut columns = (0..headers.len())
+        .map(|_| ColumnAcc::default())
+        .collect::<Vec<_>>();
+
+    let mut rows = 0;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        rows += 1;
+        let row = types.parse(&record)?;
+        for ((acc, raw), cell) in columns.iter_mut().zip(record.iter()).zip(row) {
+            acc.add(raw, cell);
+        }
+    }
+
+    let profile = Profile {
+        rows,
+        columns: headers
+            .iter()
+            .zip(columns)
+            .map(|(name, acc)| acc.finish(name, top))
+            .collect(),
+    };
+    let content = match format {
+        None => profile.to_string(),
+        Some(OutputFormat::Json) => serde_json::to_string_pretty(&profile)? + "\n",
+        Some(OutputFormat::Yaml) => serde_yaml::to_string(&profile)?,
+        Some(OutputFormat::Toml) => toml::to_string(&profile)?,
+        Some(format @ (OutputFormat::Xlsx | OutputFormat::Parquet)) => {
+            return Err(anyhow!("Stats cannot be written as {}", format));
+        }
+        Some(OutputFormat::Ndjson) => {
+            let mut lines = String::new();
+            for column in &profile.columns {
+                lines.push_str(&serde_json::to_string(column)?);
+                lines.push('\n');
+            }
+            lines
+        }
+    };
+    let mut out = get_writer(output)?;
+    out.write_all(content.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+impl ColumnAcc {
+    fn add(&mut self, raw: &str, cell: Cell) {
+        let Some(kind) = cell.kind() else {
+            self.nulls += 1;
+            return;
+        };
+        self.kind = Some(match self.kind {
+            None => kind,
+            Some(prev) => prev.merge(kind),
+        });
+
+        let number = match cell {
+            Cell::Int(v) => Some(v as f64),
+            Cell::Float(v) => Some(v),
+            _ => None,
+        };
+        if let Some(v) = number {
+            self.sum += v;
+            self.numbers += 1;
+        }
+        if kind.is_ordered() {
+            if self
+                .min
+                .as_ref()
+                .is_none_or(|min| cell.compare(min).is_lt())
+            {
+                self.min = Some(cell.clone());
+            }
+            if self
+                .max
+                .as_ref()
+                .is_none_or(|max| cell.compare(max).is_gt())
+            {
+                self.max = Some(cell);
+            }
+        }
+        if raw.chars().count() > self.longest.chars().count() {
+            self.longest = raw.into();
+        }
+        *self.counts.entry(raw.into()).or_default() += 1;
+    }
+
+    fn finish(self, name: &str, top: usize) -> ColumnStats {
+        let ordered = self.kind.is_some_and(Kind::is_ordered);
+        let numeric = matches!(self.kind, Some(Kind::Int | Kind::Float));
+        let mut counts = self.counts.into_iter().collect::<Vec<_>>();
+        // 次数相同时按值排序，保证输出稳定
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        ColumnStats {
+            name: name.into(),
+            ty: self.kind.map_or("null", Kind::name).into(),
+            nulls: self.nulls,
+            distinct: counts.len() as u64,
+            min: self.min.filter(|_| ordered).map(Cell::into_json),
+            max: self.max.filter(|_| ordered).map(Cell::into_json),
+            mean: (numeric && self.numbers > 0).then(|| self.sum / self.numbers as f64),
+            longest: self.longest,
+            top: counts
+                .into_iter()
+                .take(top)
+                .map(|(value, count)| ValueCount { value, count })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const HEADERS: [&str; 9] = [
+            "column", "type", "nulls", "distinct", "min", "max", "mean", "longest", "top",
+        ];
+        let text = |v: &Option<Value>| match v {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        };
+        let table = self
+            .columns
+            .iter()
+            .map(|c| {
+                let top = c
+                    .top
+                    .iter()
+                    .map(|v| format!("{} ({})", v.value, v.count))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                [
+                    c.name.clone(),
+                    c.ty.clone(),
+                    c.nulls.to_string(),
+                    c.distinct.to_string(),
+                    text(&c.min),
+                    text(&c.max),
+                    c.mean.map(|m| format!("{:.2}", m)).unwrap_or_default(),
+                    c.longest.clone(),
+                    top,
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut widths = HEADERS.map(|h| h.chars().count());
+        for row in &table {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        writeln!(f, "rows: {}", self.rows)?;
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String]| {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())
+        };
+        write_row(f, &HEADERS.map(String::from))?;
+        for row in &table {
+            write_row(f, row)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{CellType, CsvTypeOpts};
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use csv::StringRecord;
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+const DATETIME_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    String(String),
+}
+
+/// The type of a non-null cell, columns with mixed kinds widen via `merge`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Int,
+    Float,
+    Bool,
+    Date,
+    DateTime,
+    String,
+}
+
+/// Column types resolved against the header row, `--type` overrides win over inference.
+pub struct ColumnTypes {
+    headers: StringRecord,
+    types: Vec<CellType>,
+}
+
+impl ColumnTypes {
+    pub fn new(headers: &StringRecord, opts: &CsvTypeOpts) -> Result<Self> {
+        let default = if opts.raw {
+            CellType::String
+        } else {
+            CellType::Auto
+        };
+        let mut types = vec![default; headers.len()];
+        for (column, ty) in opts.overrides.iter().flat_map(|o| &o.0) {
+            let idx = headers
+                .iter()
+                .position(|h| h == column)
+                .ok_or_else(|| anyhow!("Unknown column {:?} in --type", column))?;
+            types[idx] = ty.clone();
+        }
+        Ok(Self {
+            headers: headers.clone(),
+            types,
+        })
+    }
+
+    pub fn parse(&self, record: &StringRecord) -> Result<Vec<Cell>> {
+        let line = record.position().map_or(0, |p| p.line());
+        record
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let ty = self.types.get(i).unwrap_or(&CellType::Auto);
+                Cell::parse(cell, ty).map_err(|e| {
+                    let column = self.headers.get(i).unwrap_or_default();
+                    anyhow!("line {}, column {:?}: {}", line, column, e)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Zip the header names with a parsed row into a json object.
+pub fn row_to_json(headers: &StringRecord, row: Vec<Cell>) -> Value {
+    let map = headers
+        .iter()
+        .zip(row)
+        .map(|(h, cell)| (h.to_string(), cell.into_json()))
+        .collect::<Map<_, _>>();
+    Value::Object(map)
+}
+
+pub fn row_to_toml(headers: &StringRecord, row: Vec<Cell>) -> toml::Table {
+    headers
+        .iter()
+        .zip(row)
+        // toml has no null, empty cells are left out of the table
+        .filter_map(|(h, cell)| Some((h.to_string(), cell.into_toml()?)))
+        .collect()
+}
+
+impl Cell {
+    pub fn parse(cell: &str, ty: &CellType) -> Result<Self> {
+        if cell.is_empty() {
+            return Ok(match ty {
+                CellType::String => Cell::String(String::new()),
+                _ => Cell::Null,
+            });
+        }
+        let value = match ty {
+            CellType::Auto => Cell::infer(cell),
+            CellType::String => Cell::String(cell.into()),
+            CellType::Int => Cell::Int(
+                cell.trim()
+                    .parse()
+                    .map_err(|e| anyhow!("{:?}: {}", cell, e))?,
+            ),
+            CellType::Float => Cell::Float(
+                cell.trim()
+                    .parse()
+                    .map_err(|e| anyhow!("{:?}: {}", cell, e))?,
+            ),
+            CellType::Bool => {
+                Cell::Bool(parse_bool(cell).ok_or_else(|| anyhow!("invalid bool {:?}", cell))?)
+            }
+            CellType::Date(None) => {
+                parse_iso_date(cell).ok_or_else(|| anyhow!("invalid ISO date {:?}", cell))?
+            }
+            CellType::Date(Some(fmt)) => parse_date(cell, fmt)?,
+        };
+        Ok(value)
+    }
+
+    pub fn infer(cell: &str) -> Self {
+        if cell.is_empty() {
+            Cell::Null
+        } else if let Some(v) = parse_int(cell) {
+            Cell::Int(v)
+        } else if let Some(v) = parse_float(cell) {
+            Cell::Float(v)
+        } else if let Ok(v) = cell.parse::<bool>() {
+            Cell::Bool(v)
+        } else if let Some(v) = parse_iso_date(cell) {
+            v
+        } else {
+            Cell::String(cell.into())
+        }
+    }
+
+    pub fn kind(&self) -> Option<Kind> {
+        match self {
+            Cell::Null => None,
+            Cell::Int(_) => Some(Kind::Int),
+            Cell::Float(_) => Some(Kind::Float),
+            Cell::Bool(_) => Some(Kind::Bool),
+            Cell::Date(_) => Some(Kind::Date),
+            Cell::DateTime(_) => Some(Kind::DateTime),
+            Cell::String(_) => Some(Kind::String),
+        }
+    }
+
+    /// A total order for sorting mixed columns: cells of different kinds order
+    /// by kind, null < number < bool < date < string, then by value within it.
+    pub fn compare(&self, other: &Cell) -> Ordering {
+        match (self, other) {
+            (Cell::Null, Cell::Null) => Ordering::Equal,
+            (Cell::Int(a), Cell::Int(b)) => a.cmp(b),
+            (Cell::Int(_) | Cell::Float(_), Cell::Int(_) | Cell::Float(_)) => {
+                self.as_f64().total_cmp(&other.as_f64())
+            }
+            (Cell::Bool(a), Cell::Bool(b)) => a.cmp(b),
+            (Cell::Date(a), Cell::Date(b)) => a.cmp(b),
+            (Cell::Date(_) | Cell::DateTime(_), Cell::Date(_) | Cell::DateTime(_)) => {
+                self.as_datetime().cmp(&other.as_datetime())
+            }
+            (Cell::String(a), Cell::String(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    // 不同类型之间的先后顺序
+    fn rank(&self) -> u8 {
+        match self {
+            Cell::Null => 0,
+            Cell::Int(_) | Cell::Float(_) => 1,
+            Cell::Bool(_) => 2,
+            Cell::Date(_) | Cell::DateTime(_) => 3,
+            Cell::String(_) => 4,
+        }
+    }
+
+    fn as_datetime(&self) -> Option<NaiveDateTime> {
+        match self {
+            Cell::Date(v) => Some(v.and_time(Default::default())),
+            Cell::DateTime(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            Cell::Int(v) => *v as f64,
+            Cell::Float(v) => *v,
+            _ => f64::NAN,
+        }
+    }
+
+    pub fn into_json(self) -> Value {
+        match self {
+            Cell::Null => Value::Null,
+            Cell::Bool(v) => Value::Bool(v),
+            Cell::Int(v) => Value::Number(v.into()),
+            Cell::Float(v) => Number::from_f64(v).map_or(Value::Null, Value::Number),
+            Cell::String(v) => Value::String(v),
+            date => Value::String(date.to_string()),
+        }
+    }
+
+    pub fn into_toml(self) -> Option<toml::Value> {
+        let value = match self {
+            Cell::Null => return None,
+            Cell::Bool(v) => toml::Value::Boolean(v),
+            Cell::Int(v) => toml::Value::Integer(v),
+            Cell::Float(v) => toml::Value::Float(v),
+            Cell::String(v) => toml::Value::String(v),
+            date => {
+                let s = date.to_string();
+                s.parse()
+                    .map(toml::Value::Datetime)
+                    .unwrap_or(toml::Value::String(s))
+            }
+        };
+        Some(value)
+    }
+}
+
+impl Kind {
+    pub fn merge(self, other: Kind) -> Kind {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Kind::Int | Kind::Float, Kind::Int | Kind::Float) => Kind::Float,
+            (Kind::Date | Kind::DateTime, Kind::Date | Kind::DateTime) => Kind::DateTime,
+            _ => Kind::String,
+        }
+    }
+
+    pub fn is_ordered(self) -> bool {
+        !matches!(self, Kind::Bool | Kind::String)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Int => "int",
+            Kind::Float => "float",
+            Kind::Bool => "bool",
+            Kind::Date => "date",
+            Kind::DateTime => "datetime",
+            Kind::String => "string",
+        }
+    }
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cell::Null => Ok(()),
+            Cell::Bool(v) => write!(f, "{}", v),
+            Cell::Int(v) => write!(f, "{}", v),
+            Cell::Float(v) => write!(f, "{}", v),
+            Cell::Date(v) => write!(f, "{}", v.format("%Y-%m-%d")),
+            Cell::DateTime(v) => write!(f, "{}", v.format("%Y-%m-%dT%H:%M:%S%.f")),
+            Cell::String(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// "007" is an identifier rather than a number, keep it as a string
+fn has_leading_zero(cell: &str) -> bool {
+    let digits = cell.trim_start_matches(['-', '+']);
+    digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.")
+}
+
+fn parse_int(cell: &str) -> Option<i64> {
+    if has_leading_zero(cell) {
+        return None;
+    }
+    cell.parse().ok()
+}
+
+fn parse_float(cell: &str) -> Option<f64> {
+    // only plain decimal notation, "inf"/"NaN" stay strings
+    if has_leading_zero(cell) || !cell.bytes().any(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    cell.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn parse_bool(cell: &str) -> Option<bool> {
+    match cell.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_iso_date(cell: &str) -> Option<Cell> {
+    if let Ok(v) = NaiveDate::parse_from_str(cell, "%Y-%m-%d") {
+        return Some(Cell::Date(v));
+    }
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(cell, fmt).ok())
+        .map(Cell::DateTime)
+}
+
+// 允许日期后带有空格分隔的附注，如 "Apr 18, 1990 (29)"
+fn parse_date(cell: &str, fmt: &str) -> Result<Cell> {
+    if let Ok(v) = NaiveDateTime::parse_from_str(cell, fmt) {
+        return Ok(Cell::DateTime(v));
+    }
+    match NaiveDate::parse_and_remainder(cell, fmt) {
+        Ok((v, rest)) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            Ok(Cell::Date(v))
+        }
+        _ => Err(anyhow!("{:?} does not match date format {:?}", cell, fmt)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_is_a_total_order_on_mixed_columns() {
+        let mut cells = (0..5000)
+            .map(|i| match i % 7 {
+                0 => Cell::infer(&(i * 37 % 1000).to_string()),
+                1 => Cell::infer(&format!("{}.5", i % 300)),
+                2 => Cell::infer(&format!("{}x", i % 500)),
+                3 => Cell::infer(if i % 2 == 0 { "true" } else { "false" }),
+                4 => Cell::infer(&format!("2024-01-{:02}", i % 28 + 1)),
+                5 => Cell::infer(&format!("2024-01-{:02} 12:00:00", i % 28 + 1)),
+                _ => Cell::Null,
+            })
+            .collect::<Vec<_>>();
+        cells.sort_by(Cell::compare);
+
+        for pair in cells.windows(2) {
+            assert_ne!(pair[0].compare(&pair[1]), Ordering::Greater, "{:?}", pair);
+        }
+        let ranks = cells.iter().map(Cell::rank).collect::<Vec<_>>();
+        assert!(ranks.windows(2).all(|r| r[0] <= r[1]));
+        assert_eq!(Cell::Int(2).compare(&Cell::Int(10)), Ordering::Less);
+        assert_eq!(Cell::Float(2.5).compare(&Cell::Int(10)), Ordering::Less);
+        assert_eq!(
+            Cell::Int(10).compare(&Cell::String("1x".into())),
+            Ordering::Less
+        );
+        assert_eq!(
+            Cell::String("1x".into()).compare(&Cell::Int(2)),
+            Ordering::Greater
+        );
+    }
+}
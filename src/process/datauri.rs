@@ -0,0 +1,194 @@
+use super::b64::{decode_stream, encode_stream, BUF_SIZE};
+use crate::{get_reader, get_writer, Base64Format};
+use anyhow::{anyhow, Result};
+use std::{
+    io::{BufRead, BufReader, BufWriter, Cursor, Read, Write},
+    path::Path,
+};
+
+// (扩展名, MIME 类型)，反向查找时取第一个匹配的扩展名
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("svg", "image/svg+xml"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("pdf", "application/pdf"),
+    ("wasm", "application/wasm"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("json", "application/json"),
+    ("js", "text/javascript"),
+    ("css", "text/css"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("csv", "text/csv"),
+    ("txt", "text/plain"),
+];
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+// 最长的检查到 BMP 的 DIB 头长度字段，即前 18 字节
+const SNIFF_LEN: u64 = 32;
+
+/// Emit `data:<mime>;base64,<payload>`. Without `mime` the type is sniffed from
+/// the magic bytes, then from the file extension.
+pub fn process_datauri_encode(input: &str, output: &str, mime: Option<&str>) -> Result<()> {
+    let mut input_reader = get_reader(input)?;
+    // 管道一次 read 可能只返回几个字节，先读满文件头再判断类型
+    let mut head = Vec::new();
+    (&mut input_reader).take(SNIFF_LEN).read_to_end(&mut head)?;
+    let mime = match mime {
+        Some(mime) => mime.to_string(),
+        None => sniff_mime(&head)
+            .or_else(|| mime_from_extension(input))
+            .unwrap_or(DEFAULT_MIME)
+            .to_string(),
+    };
+    let mut reader = BufReader::with_capacity(BUF_SIZE, Cursor::new(head).chain(input_reader));
+    let mut writer = BufWriter::with_capacity(BUF_SIZE, get_writer(output)?);
+    write!(writer, "data:{};base64,", mime)?;
+    encode_stream(&mut reader, &mut writer, Base64Format::Standard, None)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the payload of a data uri to `output`, by default `output.<ext>` for
+/// its media type. Returns the media type and the file written.
+pub fn process_datauri_decode(input: &str, output: Option<&str>) -> Result<(String, String)> {
+    let mut reader = BufReader::with_capacity(BUF_SIZE, get_reader(input)?);
+    let mut header = Vec::new();
+    reader.read_until(b',', &mut header)?;
+    let header = String::from_utf8(header)
+        .ok()
+        .and_then(|h| {
+            let h = h.trim_start();
+            h.get(..5)
+                .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))?;
+            Some(h[5..].strip_suffix(',')?.to_string())
+        })
+        .ok_or_else(|| anyhow!("Input is not a data uri"))?;
+
+    // data:[<mediatype>][;base64],<data>
+    let (media_type, base64) = match header.strip_suffix(";base64") {
+        Some(media_type) => (media_type, true),
+        None => (header.as_str(), false),
+    };
+    let media_type = match media_type {
+        "" => "text/plain;charset=US-ASCII".to_string(),
+        media_type => media_type.to_string(),
+    };
+    let output = match output {
+        Some(output) => output.to_string(),
+        None => format!("output.{}", extension_for_mime(&media_type)),
+    };
+
+    let mut writer = BufWriter::with_capacity(BUF_SIZE, get_writer(&output)?);
+    if base64 {
+        decode_stream(reader, &mut writer, Base64Format::Auto)?;
+    } else {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        writer.write_all(&percent_decode(data.trim_ascii_end())?)?;
+    }
+    writer.flush()?;
+    Ok((media_type, output))
+}
+
+fn sniff_mime(head: &[u8]) -> Option<&'static str> {
+    let mime = match head {
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => "audio/wav",
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f', ..] => "image/avif",
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => "video/mp4",
+        [b'B', b'M', ..] if is_bmp(head) => "image/bmp",
+        [0, 0, 1, 0, ..] if is_ico(head) => "image/x-icon",
+        [b'w', b'O', b'F', b'F', ..] => "font/woff",
+        [b'w', b'O', b'F', b'2', ..] => "font/woff2",
+        [0, 1, 0, 0, 0, ..] => "font/ttf",
+        [b'O', b'T', b'T', b'O', ..] => "font/otf",
+        [b'%', b'P', b'D', b'F', b'-', ..] => "application/pdf",
+        [0, b'a', b's', b'm', ..] => "application/wasm",
+        [b'P', b'K', 3, 4, ..] => "application/zip",
+        [0x1f, 0x8b, ..] => "application/gzip",
+        [b'I', b'D', b'3', ..] | [0xff, 0xfb | 0xf3 | 0xf2, ..] => "audio/mpeg",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => "video/webm",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+// "BM" 太短，文本文件也可能以它开头：还要求保留字段为 0 且 DIB 头长度合法
+fn is_bmp(head: &[u8]) -> bool {
+    let u32_at = |i: usize| Some(u32::from_le_bytes(head.get(i..i + 4)?.try_into().ok()?));
+    u32_at(6) == Some(0)
+        && matches!(u32_at(14), Some(12 | 40 | 52 | 56 | 64 | 108 | 124))
+        && u32_at(2).is_some_and(|size| size >= 26)
+}
+
+// 至少一个图标，第一个目录项的保留字节为 0、planes 为 0 或 1
+fn is_ico(head: &[u8]) -> bool {
+    match head.get(4..12) {
+        Some(&[count_lo, count_hi, _, _, _, reserved, planes, planes_hi]) => {
+            u16::from_le_bytes([count_lo, count_hi]) > 0
+                && reserved == 0
+                && planes <= 1
+                && planes_hi == 0
+        }
+        _ => false,
+    }
+}
+
+// svg、css 等文本格式没有 magic bytes，只能看扩展名
+fn mime_from_extension(input: &str) -> Option<&'static str> {
+    let ext = Path::new(input).extension()?.to_str()?.to_ascii_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, mime)| *mime)
+}
+
+fn extension_for_mime(media_type: &str) -> &'static str {
+    let mime = media_type.split(';').next().unwrap_or_default().trim();
+    MIME_TYPES
+        .iter()
+        .find(|(_, m)| m.eq_ignore_ascii_case(mime))
+        .map_or("bin", |(ext, _)| *ext)
+}
+
+fn percent_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'%' {
+            let hex = data
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid percent escape at offset {}", i))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(data[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
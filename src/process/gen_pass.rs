@@ -1,46 +1,260 @@
-use anyhow::Result;
-use rand::seq::SliceRandom;
-const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
-const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const NUMBER: &[u8] = b"123456789";
-const SYMBOL: &[u8] = b"!@#$%^&*_";
+use super::passwd::process_passwd_hash;
+use crate::{get_writer, CharsetOpts, HashAlgorithm, HashParams, PasswordFormat};
+use anyhow::{anyhow, Result};
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+use std::{collections::HashSet, fs, io::Write};
 
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const NUMBER: &str = "0123456789";
+// 不含引号、反斜杠和反引号，避免在 shell 中需要转义
+const SYMBOL: &str = "!#$%&()*+,-./:;<=>?@[]^_{|}~";
+const AMBIGUOUS: &str = "0O1lI|";
+// 长度、单词数和批量个数的上限，过大的值会在分配内存时 panic
+const MAX_COUNT: usize = 1 << 20;
+const EFF_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+#[derive(Debug, Clone)]
+pub struct Password {
+    pub value: String,
+    // 按字符池大小和长度计算的熵（bits）
+    pub entropy: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordEntry {
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// Random password from the enabled classes, or from `--charset`. Every enabled
+/// class contributes at least one character, or its `--min-*` count.
 pub fn process_genpass(
-    length: u8,
+    length: usize,
     upper: bool,
     lower: bool,
     number: bool,
     symbol: bool,
-) -> Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
-    let mut chars = Vec::new();
+    opts: &CharsetOpts,
+) -> Result<Password> {
+    if length == 0 {
+        return Err(anyhow!("Length must be at least 1"));
+    }
+    if length > MAX_COUNT {
+        return Err(anyhow!("Length must be at most {}", MAX_COUNT));
+    }
+    let excluded =
+        |c: &char| opts.exclude.contains(*c) || (opts.no_ambiguous && AMBIGUOUS.contains(*c));
+    let classes = match &opts.charset {
+        Some(charset) => vec![("--charset", charset.as_str(), true, 0)],
+        None => vec![
+            ("uppercase", UPPER, upper, opts.min_upper),
+            ("lowercase", LOWER, lower, opts.min_lower),
+            ("number", NUMBER, number, opts.min_digits),
+            (
+                "symbol",
+                opts.symbols.as_deref().unwrap_or(SYMBOL),
+                symbol,
+                opts.min_symbols,
+            ),
+        ],
+    };
 
-    if upper {
-        chars.extend_from_slice(UPPER);
-        password.push(*UPPER.choose(&mut rng).expect("UPPER won't be empty"));
+    let mut pool = Vec::new();
+    let mut required = Vec::new();
+    for (name, chars, enabled, min) in classes {
+        if !enabled {
+            if min > 0 {
+                return Err(anyhow!(
+                    "Minimum {} count requires {} characters",
+                    name,
+                    name
+                ));
+            }
+            continue;
+        }
+        let mut chars = chars.chars().filter(|c| !excluded(c)).collect::<Vec<_>>();
+        chars.sort_unstable();
+        chars.dedup();
+        if chars.is_empty() {
+            return Err(anyhow!("No {} characters left after exclusions", name));
+        }
+        let min = if opts.charset.is_some() {
+            min
+        } else {
+            min.max(1)
+        };
+        required.push((chars.clone(), min));
+        pool.extend(chars);
+    }
+    pool.sort_unstable();
+    pool.dedup();
+    if pool.is_empty() {
+        return Err(anyhow!("At least one character class must be enabled"));
     }
-    if lower {
-        chars.extend_from_slice(LOWER);
-        password.push(*LOWER.choose(&mut rng).expect("LOWER won't be empty"));
+    let min_total = required
+        .iter()
+        .fold(0usize, |total, (_, min)| total.saturating_add(*min));
+    if min_total > length {
+        return Err(anyhow!(
+            "Length {} is shorter than the {} required characters",
+            length,
+            min_total
+        ));
     }
-    if number {
-        chars.extend_from_slice(NUMBER);
-        password.push(*NUMBER.choose(&mut rng).expect("NUMBER won't be empty"));
+
+    let mut rng = rand::thread_rng();
+    let mut password = Vec::with_capacity(length);
+    for (chars, min) in &required {
+        for _ in 0..*min {
+            password.push(*chars.choose(&mut rng).expect("class won't be empty"));
+        }
     }
-    if symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(*SYMBOL.choose(&mut rng).expect("SYMBOL won't be empty"));
+    while password.len() < length {
+        password.push(*pool.choose(&mut rng).expect("pool won't be empty"));
     }
+    password.shuffle(&mut rng);
 
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars
-            .choose(&mut rng)
-            .expect("chars won't be empty in this context");
-        password.push(*c);
+    Ok(Password {
+        value: password.into_iter().collect(),
+        entropy: length as f64 * (pool.len() as f64).log2(),
+    })
+}
+
+/// Diceware-style passphrase from the EFF large wordlist, or from `wordlist`
+/// (one word per line, an optional leading dice number is ignored).
+pub fn process_genphrase(
+    words: usize,
+    separator: &str,
+    wordlist: Option<&str>,
+    capitalize: bool,
+    digit: bool,
+    symbol: bool,
+) -> Result<Password> {
+    if words == 0 {
+        return Err(anyhow!("Number of words must be at least 1"));
+    }
+    if words > MAX_COUNT {
+        return Err(anyhow!("Number of words must be at most {}", MAX_COUNT));
+    }
+    let content = match wordlist {
+        Some(path) => fs::read_to_string(path)?,
+        None => EFF_WORDLIST.to_string(),
+    };
+    let mut list = content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect::<Vec<_>>();
+    // 重复的单词会让熵的估算偏高
+    list.sort_unstable();
+    list.dedup();
+    if list.len() < 2 {
+        return Err(anyhow!("Wordlist must contain at least 2 words"));
     }
 
-    password.shuffle(&mut rng);
+    let mut rng = rand::thread_rng();
+    let mut phrase = (0..words)
+        .map(|_| {
+            let word = *list.choose(&mut rng).expect("list won't be empty");
+            if capitalize {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<String>>();
+    let mut entropy = words as f64 * (list.len() as f64).log2();
+    // 数字和符号追加到随机选中的单词末尾
+    for (enabled, table) in [(digit, NUMBER), (symbol, SYMBOL)] {
+        if enabled && !phrase.is_empty() {
+            let i = rng.gen_range(0..phrase.len());
+            let c = table.chars().nth(rng.gen_range(0..table.len()));
+            phrase[i].push(c.expect("table is ascii"));
+            entropy += ((table.len() * phrase.len()) as f64).log2();
+        }
+    }
+    Ok(Password {
+        value: phrase.join(separator),
+        entropy,
+    })
+}
+
+/// Call `generate` until there are `count` distinct passwords, then write them
+/// with their entropy and hash when asked for.
+pub fn process_genpass_batch(
+    output: &str,
+    format: PasswordFormat,
+    count: usize,
+    with_entropy: bool,
+    hash: Option<HashAlgorithm>,
+    mut generate: impl FnMut() -> Result<Password>,
+) -> Result<()> {
+    if count > MAX_COUNT {
+        return Err(anyhow!("Count must be at most {}", MAX_COUNT));
+    }
+    let mut seen = HashSet::with_capacity(count);
+    let mut entries = Vec::with_capacity(count);
+    // 字符集太小时可能无法生成足够多不重复的密码
+    let mut attempts = count.saturating_mul(10).saturating_add(100);
+    while entries.len() < count {
+        if attempts == 0 {
+            return Err(anyhow!(
+                "Could not generate {} unique passwords, increase the length or the charset",
+                count
+            ));
+        }
+        attempts -= 1;
+        let password = generate()?;
+        if !seen.insert(password.value.clone()) {
+            continue;
+        }
+        entries.push(PasswordEntry {
+            hash: hash
+                .map(|algorithm| {
+                    process_passwd_hash(&password.value, algorithm, &HashParams::default())
+                })
+                .transpose()?,
+            entropy: with_entropy.then(|| (password.entropy * 10.0).round() / 10.0),
+            password: password.value,
+        });
+    }
 
-    Ok(String::from_utf8(password)?)
+    let mut out = get_writer(output)?;
+    match format {
+        PasswordFormat::Text => {
+            for entry in &entries {
+                let mut line = entry.password.clone();
+                if let Some(entropy) = entry.entropy {
+                    line.push_str(&format!("\t{:.1}", entropy));
+                }
+                if let Some(hash) = &entry.hash {
+                    line.push('\t');
+                    line.push_str(hash);
+                }
+                writeln!(out, "{}", line)?;
+            }
+        }
+        PasswordFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for entry in &entries {
+                writer.serialize(entry)?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        PasswordFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &entries)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
 }
@@ -47,13 +47,11 @@ async fn file_handler(
     let p = std::path::Path::new(&state.path).join(path);
     info!("{:?}", p);
     if !p.exists() {
-        return gen_resp(p, PathType::NotFound).await;
+        gen_resp(p, PathType::NotFound).await
+    } else if p.is_dir() {
+        gen_resp(p, PathType::IsDir).await
     } else {
-        if p.is_dir() {
-            return gen_resp(p, PathType::IsDir).await;
-        } else {
-            return gen_resp(p, PathType::IsFile).await;
-        }
+        gen_resp(p, PathType::IsFile).await
     }
 }
 
@@ -18,17 +18,18 @@ pub fn process_jwt_sign(sub: impl ToString, aud: impl ToString, exp: &str) -> Re
 }
 
 pub fn process_jwt_verify(token: &str) -> Result<bool> {
-    let mut options = VerificationOptions::default();
-    // 不接受将来的时间
-    options.accept_future = false;
-    // 时间容忍度为0
-    options.time_tolerance = Some(Duration::from_secs(0).into());
+    let options = VerificationOptions {
+        // 不接受将来的时间
+        accept_future: false,
+        // 时间容忍度为0
+        time_tolerance: Some(Duration::from_secs(0).into()),
+        ..Default::default()
+    };
     let key = fs::read("./fixtures/jwt.key")?;
     let key = HS256Key::from_bytes(key.as_slice());
-    let ret = match key.verify_token::<NoCustomClaims>(&token, Some(options)) {
-        Ok(_) => true,
-        Err(_) => false,
-    };
+    let ret = key
+        .verify_token::<NoCustomClaims>(token, Some(options))
+        .is_ok();
     Ok(ret)
 }
 
@@ -36,7 +37,7 @@ fn parse_duration(exp: &str) -> Result<Duration> {
     let mut seconds = 0u64;
     let mut num_str = String::new();
     for c in exp.chars() {
-        if c.is_digit(10) {
+        if c.is_ascii_digit() {
             num_str.push(c);
         } else {
             let num: u64 = num_str.parse().expect("Invalid number");
@@ -1,12 +1,35 @@
 mod b64;
+mod codec;
+mod convert;
 mod csv_convert;
+mod csv_diff;
+mod csv_export;
+mod csv_join;
+mod csv_query;
+mod csv_schema;
+mod csv_stats;
+mod csv_types;
+mod datauri;
 mod gen_pass;
+mod otp;
+mod passwd;
 mod text;
 mod http_serve;
 mod jwt_process;
 pub use b64::{process_decode, process_encode};
-pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
+pub use codec::process_codec;
+pub use convert::process_convert;
+pub use csv_convert::{process_csv, process_records};
+pub use csv_diff::process_csv_diff;
+pub use csv_join::{process_csv_concat, process_csv_join};
+pub use csv_schema::process_csv_validate;
+pub use csv_stats::process_csv_stats;
+pub use datauri::{process_datauri_decode, process_datauri_encode};
+pub use gen_pass::{
+    process_genpass, process_genpass_batch, process_genphrase, Password, PasswordEntry,
+};
+pub use otp::{process_otp_code, process_otp_secret, process_otp_uri, process_otp_verify};
+pub use passwd::{process_passwd_check, process_passwd_hash, process_passwd_verify, Strength};
 pub use text::process_sign;
 pub use text::process_verify;
 pub use text::process_key_generate;
@@ -0,0 +1,134 @@
+use crate::{OtpAlgorithm, OtpOpts};
+use anyhow::{anyhow, Result};
+use data_encoding::BASE32_NOPAD;
+use hmac::{
+    digest::{core_api::BlockSizeUser, Digest, KeyInit},
+    Mac, SimpleHmac,
+};
+use rand::RngCore;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `bytes` random bytes from the same generator as `rcli genpass`, base32
+/// encoded without padding so `otp code` and authenticator apps accept it.
+pub fn process_otp_secret(bytes: usize) -> Result<String> {
+    let mut key = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut key);
+    Ok(BASE32_NOPAD.encode(&key))
+}
+
+pub fn process_otp_uri(opts: &OtpOpts, issuer: &str, account: &str) -> Result<String> {
+    let secret = normalize_secret(&opts.secret);
+    decode_secret(&secret)?;
+    let (kind, moving_factor) = match opts.counter {
+        Some(counter) => ("hotp", format!("counter={}", counter)),
+        None => ("totp", format!("period={}", opts.period)),
+    };
+    Ok(format!(
+        "otpauth://{}/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&{}",
+        kind,
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        opts.algorithm,
+        opts.digits,
+        moving_factor
+    ))
+}
+
+/// HOTP (RFC 4226) for `--counter`, otherwise TOTP (RFC 6238) at `time` or now.
+pub fn process_otp_code(opts: &OtpOpts, time: Option<u64>) -> Result<String> {
+    let key = decode_secret(&normalize_secret(&opts.secret))?;
+    let counter = match opts.counter {
+        Some(counter) => counter,
+        None => time_step(time, opts.period)?,
+    };
+    hotp(&key, counter, opts)
+}
+
+/// Returns the matching counter (or time step), TOTP accepts `window` steps
+/// around now for clock drift, HOTP looks `window` counters ahead.
+pub fn process_otp_verify(
+    opts: &OtpOpts,
+    code: &str,
+    window: u64,
+    time: Option<u64>,
+) -> Result<Option<u64>> {
+    let key = decode_secret(&normalize_secret(&opts.secret))?;
+    let code = code.trim();
+    let candidates = match opts.counter {
+        Some(counter) => counter..=counter.saturating_add(window),
+        None => {
+            let step = time_step(time, opts.period)?;
+            step.saturating_sub(window)..=step.saturating_add(window)
+        }
+    };
+    for counter in candidates {
+        if hotp(&key, counter, opts)? == code {
+            return Ok(Some(counter));
+        }
+    }
+    Ok(None)
+}
+
+fn hotp(key: &[u8], counter: u64, opts: &OtpOpts) -> Result<String> {
+    let message = counter.to_be_bytes();
+    let digest = match opts.algorithm {
+        OtpAlgorithm::Sha1 => hmac::<sha1::Sha1>(key, &message)?,
+        OtpAlgorithm::Sha256 => hmac::<sha2::Sha256>(key, &message)?,
+        OtpAlgorithm::Sha512 => hmac::<sha2::Sha512>(key, &message)?,
+    };
+    // dynamic truncation: 最后一个字节的低 4 位为偏移量
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes(digest[offset..offset + 4].try_into()?) & 0x7fff_ffff;
+    let code = value % 10u32.pow(opts.digits);
+    Ok(format!("{:0width$}", code, width = opts.digits as usize))
+}
+
+fn hmac<D>(key: &[u8], message: &[u8]) -> Result<Vec<u8>>
+where
+    D: Digest + BlockSizeUser,
+{
+    let mut mac = <SimpleHmac<D> as KeyInit>::new_from_slice(key)
+        .map_err(|e| anyhow!("Invalid secret: {}", e))?;
+    mac.update(message);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn time_step(time: Option<u64>, period: u64) -> Result<u64> {
+    let time = match time {
+        Some(time) => time,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    Ok(time / period)
+}
+
+// 身份验证器显示的密钥常带空格和小写
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let key = BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .map_err(|e| anyhow!("Secret is not valid base32: {}", e))?;
+    if key.is_empty() {
+        return Err(anyhow!("Secret is empty"));
+    }
+    Ok(key)
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
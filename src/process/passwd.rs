@@ -0,0 +1,179 @@
+use crate::{HashAlgorithm, HashParams};
+use anyhow::{anyhow, Result};
+use argon2::{
+    password_hash::{
+        self, rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Version,
+};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use serde::Serialize;
+use std::fmt;
+use zxcvbn::zxcvbn;
+
+const STRENGTH: [&str; 5] = ["very weak", "weak", "fair", "strong", "very strong"];
+
+#[derive(Debug, Serialize)]
+pub struct Strength {
+    // zxcvbn 的评分，0 到 4
+    pub score: u8,
+    pub strength: &'static str,
+    pub guesses_log10: f64,
+    pub entropy: f64,
+    pub crack_times: CrackTimes,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrackTimes {
+    pub online_throttled: String,
+    pub online: String,
+    pub offline_slow_hash: String,
+    pub offline_fast_hash: String,
+}
+
+/// Pattern based strength estimation (dictionary words, keyboard walks,
+/// repeats, dates...), `user_inputs` are treated as known words.
+pub fn process_passwd_check(password: &str, user_inputs: &[&str]) -> Result<Strength> {
+    let estimate = zxcvbn(password, user_inputs).map_err(|e| anyhow!("{}", e))?;
+    let times = estimate.crack_times();
+    let feedback = estimate.feedback().as_ref();
+    Ok(Strength {
+        score: estimate.score(),
+        strength: STRENGTH[estimate.score() as usize],
+        guesses_log10: estimate.guesses_log10(),
+        entropy: estimate.guesses_log10() * 10f64.log2(),
+        crack_times: CrackTimes {
+            online_throttled: times.online_throttling_100_per_hour().to_string(),
+            online: times.online_no_throttling_10_per_second().to_string(),
+            offline_slow_hash: times.offline_slow_hashing_1e4_per_second().to_string(),
+            offline_fast_hash: times.offline_fast_hashing_1e10_per_second().to_string(),
+        },
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+    })
+}
+
+/// Hash with a random salt into a PHC string (bcrypt uses its own `$2b$` format).
+pub fn process_passwd_hash(
+    password: &str,
+    algorithm: HashAlgorithm,
+    params: &HashParams,
+) -> Result<String> {
+    let applies = match algorithm {
+        HashAlgorithm::Argon2 => ["memory", "iterations", "parallelism"].as_slice(),
+        HashAlgorithm::Bcrypt => &["cost"],
+        HashAlgorithm::Scrypt => &["cost", "block-size", "parallelism"],
+        HashAlgorithm::Pbkdf2 => &["iterations"],
+    };
+    let given = [
+        ("cost", params.cost),
+        ("memory", params.memory),
+        ("iterations", params.iterations),
+        ("parallelism", params.parallelism),
+        ("block-size", params.block_size),
+    ];
+    if let Some((name, _)) = given
+        .iter()
+        .find(|(name, value)| value.is_some() && !applies.contains(name))
+    {
+        return Err(anyhow!("--{} does not apply to {}", name, algorithm));
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = match algorithm {
+        HashAlgorithm::Argon2 => {
+            let params = argon2::Params::new(
+                params.memory.unwrap_or(argon2::Params::DEFAULT_M_COST),
+                params.iterations.unwrap_or(argon2::Params::DEFAULT_T_COST),
+                params.parallelism.unwrap_or(argon2::Params::DEFAULT_P_COST),
+                None,
+            )
+            .map_err(|e| anyhow!("Invalid argon2 params: {}", e))?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password(password.as_bytes(), &salt)
+                .map_err(|e| anyhow!("{}", e))?
+                .to_string()
+        }
+        HashAlgorithm::Bcrypt => {
+            bcrypt::hash(password, params.cost.unwrap_or(bcrypt::DEFAULT_COST))?
+        }
+        HashAlgorithm::Scrypt => {
+            let log_n = params
+                .cost
+                .unwrap_or(scrypt::Params::RECOMMENDED_LOG_N as u32);
+            let params = scrypt::Params::new(
+                u8::try_from(log_n).map_err(|_| anyhow!("scrypt cost must be below 64"))?,
+                params.block_size.unwrap_or(scrypt::Params::RECOMMENDED_R),
+                params.parallelism.unwrap_or(scrypt::Params::RECOMMENDED_P),
+                scrypt::Params::RECOMMENDED_LEN,
+            )
+            .map_err(|e| anyhow!("Invalid scrypt params: {}", e))?;
+            Scrypt
+                .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+                .map_err(|e| anyhow!("{}", e))?
+                .to_string()
+        }
+        HashAlgorithm::Pbkdf2 => {
+            let mut pbkdf2_params = pbkdf2::Params::default();
+            if let Some(rounds) = params.iterations {
+                pbkdf2_params.rounds = rounds;
+            }
+            Pbkdf2
+                .hash_password_customized(password.as_bytes(), None, None, pbkdf2_params, &salt)
+                .map_err(|e| anyhow!("{}", e))?
+                .to_string()
+        }
+    };
+    Ok(hash)
+}
+
+/// Check `password` against a PHC string (argon2, scrypt, pbkdf2) or a bcrypt hash.
+pub fn process_passwd_verify(password: &str, hash: &str) -> Result<bool> {
+    let hash = hash.trim();
+    if hash.starts_with("$2") {
+        return Ok(bcrypt::verify(password, hash)?);
+    }
+    let parsed = PasswordHash::new(hash).map_err(|e| anyhow!("Invalid hash: {}", e))?;
+    let verifiers: [&dyn PasswordVerifier; 3] = [&Argon2::default(), &Scrypt, &Pbkdf2];
+    match parsed.verify_password(&verifiers, password) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(anyhow!("Cannot verify {} hash: {}", parsed.algorithm, e)),
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "strength: {} ({}/4)", self.strength, self.score)?;
+        writeln!(f, "entropy: {:.1} bits", self.entropy)?;
+        writeln!(f, "crack time:")?;
+        writeln!(
+            f,
+            "  online, throttled (100/h): {}",
+            self.crack_times.online_throttled
+        )?;
+        writeln!(f, "  online (10/s): {}", self.crack_times.online)?;
+        writeln!(
+            f,
+            "  offline, slow hash (1e4/s): {}",
+            self.crack_times.offline_slow_hash
+        )?;
+        writeln!(
+            f,
+            "  offline, fast hash (1e10/s): {}",
+            self.crack_times.offline_fast_hash
+        )?;
+        if let Some(warning) = &self.warning {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        for suggestion in &self.suggestions {
+            writeln!(f, "- {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
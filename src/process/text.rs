@@ -1,4 +1,4 @@
-use crate::{get_reader, process_genpass, TextSignFormat};
+use crate::{get_reader, process_genpass, CharsetOpts, TextSignFormat};
 use anyhow::Result;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chacha20poly1305::{aead::Aead, AeadCore, ChaCha20Poly1305, KeyInit, Nonce};
@@ -174,7 +174,7 @@ impl TextDecrypt for Chacha20poly1305EncryptAndDecrypt {
         let nonce = Nonce::from_slice(self.nonce.as_slice());
         let decrypt = self
             .cipher
-            .decrypt(&nonce, data.as_slice())
+            .decrypt(nonce, data.as_slice())
             .map_err(|e| anyhow::anyhow!(e))?;
         Ok(decrypt.to_vec())
     }
@@ -254,14 +254,14 @@ impl KeyLoader for Chacha20poly1305EncryptAndDecrypt {
         let nonce_key = fs::read(path)?;
         // 由于nonce固定为96 bits，所以这里分割前12字节为nonce，后面即为key
         let (nonce_bytes, key) = nonce_key.split_at(12);
-        Self::try_new(&key, nonce_bytes)
+        Self::try_new(key, nonce_bytes)
     }
 }
 
 impl KeyGenerator for Blake3 {
     fn generate() -> Result<Vec<Vec<u8>>> {
-        let key = process_genpass(32, true, true, true, true)?;
-        let key = key.as_bytes().to_vec();
+        let key = process_genpass(32, true, true, true, true, &CharsetOpts::default())?;
+        let key = key.value.into_bytes();
         Ok(vec![key])
     }
 }
@@ -1,5 +1,9 @@
-use std::{fs::File, io::Read};
 use anyhow::Result;
+use encoding_rs::{Decoder, DecoderResult, Encoding, UTF_8};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+};
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     let reader: Box<dyn Read> = if input == "-" {
@@ -8,4 +12,109 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
         Box::new(File::open(input)?)
     };
     Ok(reader)
-}
\ No newline at end of file
+}
+
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(std::io::stdout())
+    } else {
+        Box::new(File::create(output)?)
+    };
+    Ok(writer)
+}
+
+/// Transcodes `inner` to UTF-8, a BOM overrides `encoding`.
+/// Malformed input is an error pointing at its line and byte offset instead of
+/// being replaced with U+FFFD.
+pub struct DecodeReader<R> {
+    inner: R,
+    decoder: Decoder,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    out: Box<[u8]>,
+    out_start: usize,
+    out_end: usize,
+    // 已解码的原始字节数与当前行号，用于定位错误
+    offset: u64,
+    line: u64,
+    eof: bool,
+    done: bool,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> DecodeReader<R> {
+    pub fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            decoder: encoding.new_decoder(),
+            buf: vec![0; 8192].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            out: vec![0; 8192].into_boxed_slice(),
+            out_start: 0,
+            out_end: 0,
+            offset: 0,
+            line: 1,
+            eof: false,
+            done: false,
+            error: None,
+        }
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, dst: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.out_start < self.out_end {
+                let n = dst.len().min(self.out_end - self.out_start);
+                dst[..n].copy_from_slice(&self.out[self.out_start..self.out_start + n]);
+                self.out_start += n;
+                return Ok(n);
+            }
+            // 先交出出错位置之前的数据，再返回错误
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if self.done {
+                return Ok(0);
+            }
+            if self.start == self.end && !self.eof {
+                self.end = self.inner.read(&mut self.buf)?;
+                self.start = 0;
+                self.eof = self.end == 0;
+            }
+            let (result, read, written) = self.decoder.decode_to_utf8_without_replacement(
+                &self.buf[self.start..self.end],
+                &mut self.out,
+                self.eof,
+            );
+            self.start += read;
+            self.offset += read as u64;
+            self.out_start = 0;
+            self.out_end = written;
+            self.line += self.out[..written].iter().filter(|&&b| b == b'\n').count() as u64;
+            match result {
+                DecoderResult::InputEmpty => self.done = self.eof,
+                DecoderResult::OutputFull => {}
+                DecoderResult::Malformed(bad, extra) => {
+                    let name = self.decoder.encoding().name();
+                    let at = self.offset - bad as u64 - extra as u64;
+                    let hint = if self.decoder.encoding() == UTF_8 {
+                        ", use --encoding if the file is not UTF-8"
+                    } else {
+                        ""
+                    };
+                    self.error = Some(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "invalid {} sequence at line {}, byte offset {}{}",
+                            name, self.line, at, hint
+                        ),
+                    ));
+                    self.done = true;
+                }
+            }
+        }
+    }
+}